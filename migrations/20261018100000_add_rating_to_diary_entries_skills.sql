@@ -0,0 +1,2 @@
+-- Links saved before ratings existed keep a NULL rating
+ALTER TABLE diary_entries_skills ADD COLUMN rating INTEGER;
@@ -0,0 +1,39 @@
+CREATE TABLE emotions(
+       id SERIAL,
+       PRIMARY KEY (id),
+       name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE urges(
+       id SERIAL,
+       PRIMARY KEY (id),
+       name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE diary_entries_emotions(
+       diary_entry_id INTEGER NOT NULL REFERENCES diary_entries (id) ON DELETE CASCADE,
+       emotion_id INTEGER NOT NULL REFERENCES emotions (id),
+       intensity INTEGER NOT NULL,
+       created_at timestamptz NOT NULL,
+       PRIMARY KEY (diary_entry_id, emotion_id)
+);
+
+CREATE TABLE diary_entries_urges(
+       diary_entry_id INTEGER NOT NULL REFERENCES diary_entries (id) ON DELETE CASCADE,
+       urge_id INTEGER NOT NULL REFERENCES urges (id),
+       intensity INTEGER NOT NULL,
+       created_at timestamptz NOT NULL,
+       PRIMARY KEY (diary_entry_id, urge_id)
+);
+
+INSERT INTO emotions (name)
+       VALUES
+       ('sadness'),
+       ('anger'),
+       ('shame'),
+       ('joy');
+
+INSERT INTO urges (name)
+       VALUES
+       ('self_harm'),
+       ('substance_use');
@@ -0,0 +1,10 @@
+-- Skills without an owner are the global catalogue; owned skills are private to that user
+ALTER TABLE skills ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE CASCADE;
+
+CREATE TABLE user_skill_preferences(
+       user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+       skill_id INTEGER NOT NULL REFERENCES skills (id) ON DELETE CASCADE,
+       hidden BOOLEAN NOT NULL DEFAULT false,
+       position INTEGER,
+       PRIMARY KEY (user_id, skill_id)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user'
+      CHECK (role IN ('user', 'admin'));
+
+-- Retired skills stay in place so diary entries that used them keep their links
+ALTER TABLE skills ADD COLUMN deleted_at timestamptz;
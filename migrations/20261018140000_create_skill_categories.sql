@@ -0,0 +1,29 @@
+CREATE TABLE skill_categories(
+       name TEXT NOT NULL,
+       PRIMARY KEY (name),
+       label TEXT NOT NULL,
+       color TEXT NOT NULL,
+       sort_order INTEGER NOT NULL,
+       description TEXT NOT NULL DEFAULT ''
+);
+
+INSERT INTO skill_categories (name, label, color, sort_order, description)
+       VALUES
+       ('mindfulness', 'Mindfulness', '#4caf50', 1,
+        'Being fully aware and present in this one moment'),
+       ('distress_tolerance', 'Distress Tolerance', '#f44336', 2,
+        'Getting through a crisis without making it worse'),
+       ('emotion_regulation', 'Emotion Regulation', '#2196f3', 3,
+        'Understanding emotions and changing the ones you want to change'),
+       ('interpersonal_effectiveness', 'Interpersonal Effectiveness', '#ff9800', 4,
+        'Asking for what you want and saying no while keeping relationships and self-respect');
+
+-- Keep any category already in use so existing skills satisfy the foreign key
+INSERT INTO skill_categories (name, label, color, sort_order)
+       SELECT DISTINCT category, initcap(replace(category, '_', ' ')), '#9e9e9e', 100
+       FROM skills
+       ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE skills
+      ADD CONSTRAINT skills_category_fkey FOREIGN KEY (category)
+      REFERENCES skill_categories (name) ON UPDATE CASCADE;
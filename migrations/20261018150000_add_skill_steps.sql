@@ -0,0 +1,9 @@
+-- A skill with a parent is one step of that skill, e.g. "describe" in DEAR MAN
+ALTER TABLE skills ADD COLUMN parent_id INTEGER REFERENCES skills (id) ON DELETE CASCADE;
+
+CREATE TABLE diary_entries_skill_steps(
+       diary_entry_id INTEGER NOT NULL REFERENCES diary_entries (id) ON DELETE CASCADE,
+       skill_id INTEGER NOT NULL REFERENCES skills (id),
+       step_id INTEGER NOT NULL REFERENCES skills (id),
+       PRIMARY KEY (diary_entry_id, skill_id, step_id)
+);
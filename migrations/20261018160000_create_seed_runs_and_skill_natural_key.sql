@@ -0,0 +1,67 @@
+-- Seed files the run_seeds binary has applied, with the md5 of the contents it ran
+CREATE TABLE seed_runs(
+       name TEXT NOT NULL,
+       PRIMARY KEY (name),
+       checksum TEXT NOT NULL,
+       applied_at timestamptz NOT NULL
+);
+
+-- Running the old seed file more than once duplicated every global skill.
+-- Fold each duplicate into the oldest copy before adding the natural key.
+CREATE TEMPORARY TABLE skill_merges AS
+       SELECT id, keep_id FROM (
+              SELECT id, min(id) OVER (PARTITION BY name) AS keep_id
+              FROM skills WHERE user_id IS NULL AND parent_id IS NULL
+       ) AS top_level WHERE id <> keep_id;
+
+UPDATE skills SET parent_id = skill_merges.keep_id
+       FROM skill_merges WHERE skills.parent_id = skill_merges.id;
+
+INSERT INTO skill_merges
+       SELECT id, keep_id FROM (
+              SELECT id, min(id) OVER (PARTITION BY name, parent_id) AS keep_id
+              FROM skills WHERE user_id IS NULL AND parent_id IS NOT NULL
+       ) AS steps WHERE id <> keep_id;
+
+UPDATE diary_entries_skills SET skills_id = skill_merges.keep_id
+       FROM skill_merges WHERE diary_entries_skills.skills_id = skill_merges.id;
+
+-- An entry that logged more than one copy of a skill now links it twice, keep the highest rating
+DELETE FROM diary_entries_skills WHERE ctid IN (
+       SELECT ctid FROM (
+              SELECT ctid, row_number() OVER (
+                     PARTITION BY diary_entry_id, skills_id
+                     ORDER BY rating DESC NULLS LAST, created_at
+              ) AS copy
+              FROM diary_entries_skills
+              WHERE skills_id IN (SELECT keep_id FROM skill_merges)
+       ) AS copies WHERE copy > 1
+);
+
+INSERT INTO diary_entries_skill_steps (diary_entry_id, skill_id, step_id)
+       SELECT diary_entry_id,
+              COALESCE(merged_skill.keep_id, skill_id),
+              COALESCE(merged_step.keep_id, step_id)
+       FROM diary_entries_skill_steps
+       LEFT JOIN skill_merges AS merged_skill ON merged_skill.id = skill_id
+       LEFT JOIN skill_merges AS merged_step ON merged_step.id = step_id
+       WHERE merged_skill.id IS NOT NULL OR merged_step.id IS NOT NULL
+       ON CONFLICT DO NOTHING;
+
+DELETE FROM diary_entries_skill_steps USING skill_merges
+       WHERE diary_entries_skill_steps.skill_id = skill_merges.id
+       OR diary_entries_skill_steps.step_id = skill_merges.id;
+
+INSERT INTO user_skill_preferences (user_id, skill_id, hidden, position)
+       SELECT user_id, skill_merges.keep_id, hidden, position
+       FROM user_skill_preferences
+       JOIN skill_merges ON skill_merges.id = user_skill_preferences.skill_id
+       ON CONFLICT DO NOTHING;
+
+DELETE FROM skills USING skill_merges WHERE skills.id = skill_merges.id;
+
+DROP TABLE skill_merges;
+
+-- Global skills are identified by name, steps by name within their parent skill
+CREATE UNIQUE INDEX skills_natural_key ON skills (name, COALESCE(parent_id, 0))
+       WHERE user_id IS NULL;
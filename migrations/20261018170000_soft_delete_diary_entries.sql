@@ -0,0 +1,2 @@
+-- Deleted entries can be restored for a while before they are purged
+ALTER TABLE diary_entries ADD COLUMN deleted_at timestamptz;
@@ -0,0 +1,83 @@
+-- Earlier versions saved a new entry every time a day was submitted.
+-- Fold each day's duplicates into its most recently updated entry before adding the unique index.
+CREATE TEMPORARY TABLE diary_entry_merges AS
+       SELECT id, keep_id FROM (
+              SELECT id, first_value(id) OVER (
+                     PARTITION BY user_id, entry_date
+                     ORDER BY updated_at DESC NULLS LAST, id DESC
+              ) AS keep_id
+              FROM diary_entries WHERE deleted_at IS NULL
+       ) AS live WHERE id <> keep_id;
+
+-- Distinct notes from every copy are kept, oldest first
+UPDATE diary_entries SET notes = merged.notes
+       FROM (
+              SELECT keep_id, string_agg(notes, E'\n\n' ORDER BY created_at) AS notes
+              FROM (
+                     SELECT keep_id, notes, min(created_at) AS created_at
+                     FROM (
+                            SELECT diary_entry_merges.keep_id, diary_entries.notes, diary_entries.created_at
+                            FROM diary_entries
+                            JOIN diary_entry_merges ON diary_entries.id = diary_entry_merges.keep_id
+                            UNION ALL
+                            SELECT diary_entry_merges.keep_id, diary_entries.notes, diary_entries.created_at
+                            FROM diary_entries
+                            JOIN diary_entry_merges ON diary_entries.id = diary_entry_merges.id
+                     ) AS copies
+                     WHERE notes IS NOT NULL AND notes <> ''
+                     GROUP BY keep_id, notes
+              ) AS distinct_notes
+              GROUP BY keep_id
+       ) AS merged
+       WHERE diary_entries.id = merged.keep_id;
+
+-- Skills only logged on a duplicate move to the kept entry, along with their steps
+INSERT INTO diary_entries_skill_steps (diary_entry_id, skill_id, step_id)
+       SELECT diary_entry_merges.keep_id, skill_id, step_id
+       FROM diary_entries_skill_steps
+       JOIN diary_entry_merges ON diary_entries_skill_steps.diary_entry_id = diary_entry_merges.id
+       WHERE NOT EXISTS (
+              SELECT 1 FROM diary_entries_skills AS kept
+              WHERE kept.diary_entry_id = diary_entry_merges.keep_id
+              AND kept.skills_id = diary_entries_skill_steps.skill_id
+       )
+       ON CONFLICT DO NOTHING;
+
+INSERT INTO diary_entries_skills (diary_entry_id, skills_id, created_at, rating)
+       SELECT DISTINCT ON (diary_entry_merges.keep_id, diary_entries_skills.skills_id)
+              diary_entry_merges.keep_id, diary_entries_skills.skills_id,
+              diary_entries_skills.created_at, diary_entries_skills.rating
+       FROM diary_entries_skills
+       JOIN diary_entry_merges ON diary_entries_skills.diary_entry_id = diary_entry_merges.id
+       WHERE NOT EXISTS (
+              SELECT 1 FROM diary_entries_skills AS kept
+              WHERE kept.diary_entry_id = diary_entry_merges.keep_id
+              AND kept.skills_id = diary_entries_skills.skills_id
+       )
+       ORDER BY diary_entry_merges.keep_id, diary_entries_skills.skills_id,
+              diary_entries_skills.created_at DESC, diary_entries_skills.diary_entry_id DESC;
+
+-- The kept entry's intensities win over a duplicate's
+INSERT INTO diary_entries_emotions (diary_entry_id, emotion_id, intensity, created_at)
+       SELECT diary_entry_merges.keep_id, emotion_id, intensity, created_at
+       FROM diary_entries_emotions
+       JOIN diary_entry_merges ON diary_entries_emotions.diary_entry_id = diary_entry_merges.id
+       ON CONFLICT DO NOTHING;
+
+INSERT INTO diary_entries_urges (diary_entry_id, urge_id, intensity, created_at)
+       SELECT diary_entry_merges.keep_id, urge_id, intensity, created_at
+       FROM diary_entries_urges
+       JOIN diary_entry_merges ON diary_entries_urges.diary_entry_id = diary_entry_merges.id
+       ON CONFLICT DO NOTHING;
+
+-- Skill links have no foreign key, the other child rows cascade
+DELETE FROM diary_entries_skills USING diary_entry_merges
+       WHERE diary_entries_skills.diary_entry_id = diary_entry_merges.id;
+DELETE FROM diary_entries USING diary_entry_merges
+       WHERE diary_entries.id = diary_entry_merges.id;
+
+DROP TABLE diary_entry_merges;
+
+-- Deleted entries are left out so a day can be written again while its old entry awaits purging
+CREATE UNIQUE INDEX diary_entries_one_per_day ON diary_entries (user_id, entry_date)
+       WHERE deleted_at IS NULL;
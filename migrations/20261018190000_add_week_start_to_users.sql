@@ -0,0 +1,3 @@
+-- The day each user's weekly diary card starts on
+ALTER TABLE users ADD COLUMN week_start TEXT NOT NULL DEFAULT 'monday'
+       CHECK (week_start IN ('monday', 'tuesday', 'wednesday', 'thursday', 'friday', 'saturday', 'sunday'));
@@ -0,0 +1,2 @@
+-- The timezone each user's days are counted in, as an IANA name such as Europe/London
+ALTER TABLE users ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
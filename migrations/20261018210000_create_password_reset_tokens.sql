@@ -0,0 +1,11 @@
+-- Forgot password tokens. Only a sha256 of each token is kept, so a leaked
+-- table can't be used to reset anyone's password.
+CREATE TABLE password_reset_tokens(
+       id SERIAL,
+       PRIMARY KEY (id),
+       user_id INT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+       token_hash TEXT NOT NULL UNIQUE,
+       expires_at timestamptz NOT NULL,
+       used_at timestamptz,
+       created_at timestamptz NOT NULL
+);
@@ -0,0 +1,4 @@
+-- New accounts start unverified until the emailed link is followed.
+-- Accounts from before verification existed are treated as verified.
+ALTER TABLE users ADD COLUMN email_verified_at timestamptz;
+UPDATE users SET email_verified_at = now();
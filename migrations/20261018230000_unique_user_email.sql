@@ -0,0 +1,12 @@
+-- Emails were never unique, so the same address could sign up more than once.
+-- The oldest account keeps the address; later ones are renamed so the owner
+-- can be contacted and the accounts merged or removed by hand.
+UPDATE users SET email = 'duplicate-' || users.id || '+' || users.email
+       FROM (
+              SELECT id, min(id) OVER (PARTITION BY lower(email)) AS keep_id
+              FROM users
+       ) AS duplicates
+       WHERE duplicates.id = users.id AND duplicates.id <> duplicates.keep_id;
+
+-- Addresses are compared case-insensitively, as mail servers do in practice
+CREATE UNIQUE INDEX users_email_key ON users (lower(email));
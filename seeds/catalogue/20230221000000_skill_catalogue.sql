@@ -35,8 +35,11 @@
 --        ('mindful_of_current_emotion', 'emotion_regulation'),
 --        ('opposite_action', 'emotion_regulation');
 
+-- Global skills are upserted by name so the file can be run again safely
 INSERT INTO skills (name, category, description)
        VALUES
        ('observe', 'mindfulness', 'observe description'),
        ('positive_experiences', 'emotion_regulation', 'positive experiences description'),
-       ('encouragement', 'distress_tolerance', 'you got this!');
+       ('encouragement', 'distress_tolerance', 'you got this!')
+       ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+       DO UPDATE SET category = EXCLUDED.category, description = EXCLUDED.description;
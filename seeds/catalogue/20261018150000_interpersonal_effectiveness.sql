@@ -0,0 +1,34 @@
+-- DEAR MAN, GIVE and FAST, each with its steps
+WITH acronyms AS (
+       INSERT INTO skills (name, category, description)
+       VALUES
+       ('DEAR MAN', 'interpersonal_effectiveness', 'getting what you want'),
+       ('GIVE', 'interpersonal_effectiveness', 'keeping the relationship'),
+       ('FAST', 'interpersonal_effectiveness', 'keeping your self-respect')
+       ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+       DO UPDATE SET category = EXCLUDED.category, description = EXCLUDED.description
+       RETURNING id, name
+)
+INSERT INTO skills (name, category, description, parent_id)
+       SELECT steps.name, 'interpersonal_effectiveness', steps.description, acronyms.id
+       FROM (VALUES
+       (1, 'DEAR MAN', 'describe', 'describe the situation'),
+       (2, 'DEAR MAN', 'express', 'express your feelings and opinions'),
+       (3, 'DEAR MAN', 'assert', 'ask for what you want or say no clearly'),
+       (4, 'DEAR MAN', 'reinforce', 'reward the person ahead of time'),
+       (5, 'DEAR MAN', 'mindful', 'keep your focus on your goals'),
+       (6, 'DEAR MAN', 'appear_confident', 'use a confident tone and manner'),
+       (7, 'DEAR MAN', 'negotiate', 'be willing to give to get'),
+       (8, 'GIVE', 'gentle', 'no attacks, threats or judging'),
+       (9, 'GIVE', 'interested', 'listen and be interested in the other person'),
+       (10, 'GIVE', 'validate', 'acknowledge the other person''s feelings'),
+       (11, 'GIVE', 'easy_manner', 'use a little humor and smile'),
+       (12, 'FAST', 'fair', 'be fair to yourself and the other person'),
+       (13, 'FAST', 'no_apologies', 'no overly apologetic behavior'),
+       (14, 'FAST', 'stick_to_values', 'stick to your own values'),
+       (15, 'FAST', 'truthful', 'don''t lie or act helpless')
+       ) AS steps(step_order, acronym, name, description)
+       JOIN acronyms ON acronyms.name = steps.acronym
+       ORDER BY steps.step_order
+       ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+       DO UPDATE SET category = EXCLUDED.category, description = EXCLUDED.description;
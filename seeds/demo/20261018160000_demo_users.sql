@@ -0,0 +1,13 @@
+-- A demo login for local development: demo@example.com / password
+INSERT INTO users (email, name, password_hash, email_verified_at)
+       SELECT 'demo@example.com', 'Demo User',
+       '$argon2id$v=19$m=15000,t=2,p=1$GfkARiTsbLloYeApX97iuQ$dBIPu5VKvyLlEQ9Mo6ReSfqVynKT2ur3OpQgWlkSfqA',
+       now()
+       WHERE NOT EXISTS (SELECT 1 FROM users WHERE email = 'demo@example.com');
+
+-- And an admin for maintaining the skill catalogue: admin@example.com / password
+INSERT INTO users (email, name, password_hash, role, email_verified_at)
+       SELECT 'admin@example.com', 'Demo Admin',
+       '$argon2id$v=19$m=15000,t=2,p=1$GfkARiTsbLloYeApX97iuQ$dBIPu5VKvyLlEQ9Mo6ReSfqVynKT2ur3OpQgWlkSfqA',
+       'admin', now()
+       WHERE NOT EXISTS (SELECT 1 FROM users WHERE email = 'admin@example.com');
@@ -1,13 +1,35 @@
 use shooting_star::configuration::get_configuration;
+use shooting_star::seeds::{load_seeds, run_seeds, SeedOptions, SeedOutcome};
 use sqlx::postgres::PgPoolOptions;
-use std::fs;
+use std::path::Path;
+
+const USAGE: &str = "Usage: run_seeds [--dry-run] [--only <name>]";
+
+fn parse_args() -> Result<SeedOptions, String> {
+    let mut options = SeedOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => options.dry_run = true,
+            "--only" => {
+                let name = args.next().ok_or("--only needs a seed name")?;
+                options.only = Some(name);
+            }
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+    Ok(options)
+}
 
 #[tokio::main]
 async fn main() {
-    let file_name = "seeds/seed_skills.sql";
-    let seed = fs::read_to_string(&file_name).expect("Unable to read file");
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
 
     let config = get_configuration().expect("Unable to read settings file");
+    let seeds = load_seeds(Path::new("seeds"), &config.seed_sets).expect("Unable to load seeds");
 
     let pool = PgPoolOptions::new()
         .max_connections(1)
@@ -15,8 +37,16 @@ async fn main() {
         .await
         .expect("Unable to connect to postgres");
 
-    sqlx::query(&seed)
-        .fetch_all(&pool)
+    let reports = run_seeds(&pool, seeds, &options)
         .await
         .expect("Unable to generate seed data");
+    for report in reports {
+        let outcome = match report.outcome {
+            SeedOutcome::Applied => "applied",
+            SeedOutcome::Reapplied => "changed, applied again",
+            SeedOutcome::UpToDate => "up to date",
+            SeedOutcome::WouldApply => "would apply (dry run)",
+        };
+        println!("{}: {}", report.key, outcome);
+    }
 }
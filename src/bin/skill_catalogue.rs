@@ -0,0 +1,128 @@
+use shooting_star::catalogue::{self, CatalogueFormat};
+use shooting_star::configuration::get_configuration;
+use sqlx::postgres::PgPoolOptions;
+use std::path::Path;
+
+const USAGE: &str = "Usage: skill_catalogue import <file> [--format yaml|json|csv] [--dry-run]
+       skill_catalogue export [--format yaml|json|csv] [--output <file>]";
+
+enum Command {
+    Import {
+        file: String,
+        format: CatalogueFormat,
+        dry_run: bool,
+    },
+    Export {
+        format: CatalogueFormat,
+        output: Option<String>,
+    },
+}
+
+// Without --format the file extension decides, falling back to yaml
+fn format_for(format: Option<CatalogueFormat>, file: Option<&str>) -> CatalogueFormat {
+    format
+        .or_else(|| {
+            file.and_then(|file| Path::new(file).extension())
+                .and_then(|extension| extension.to_str())
+                .and_then(|extension| extension.parse().ok())
+        })
+        .unwrap_or(CatalogueFormat::Yaml)
+}
+
+fn parse_args() -> Result<Command, String> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or("Missing command")?;
+    let mut file = None;
+    let mut format = None;
+    let mut dry_run = false;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = args.next().ok_or("--format needs yaml, json or csv")?;
+                format = Some(name.parse()?);
+            }
+            "--dry-run" if command == "import" => dry_run = true,
+            "--output" if command == "export" => {
+                output = Some(args.next().ok_or("--output needs a file name")?);
+            }
+            other if command == "import" && file.is_none() && !other.starts_with("--") => {
+                file = Some(other.to_string())
+            }
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+    match command.as_str() {
+        "import" => {
+            let file = file.ok_or("import needs a file")?;
+            Ok(Command::Import {
+                format: format_for(format, Some(&file)),
+                file,
+                dry_run,
+            })
+        }
+        "export" => Ok(Command::Export {
+            format: format_for(format, output.as_deref()),
+            output,
+        }),
+        other => Err(format!("Unknown command {}", other)),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let command = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+
+    let config = get_configuration().expect("Unable to read settings file");
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&config.database.connection_string())
+        .await
+        .expect("Unable to connect to postgres");
+
+    match command {
+        Command::Import {
+            file,
+            format,
+            dry_run,
+        } => {
+            let text = std::fs::read_to_string(&file).expect("Unable to read catalogue file");
+            let rows = catalogue::parse(&text, format).unwrap_or_else(|e| {
+                eprintln!("{}: {}", file, e);
+                std::process::exit(1);
+            });
+            let errors = catalogue::validate(&pool, &rows)
+                .await
+                .expect("Unable to validate catalogue");
+            if !errors.is_empty() {
+                for error in errors {
+                    eprintln!("{}: {}", file, error);
+                }
+                std::process::exit(1);
+            }
+            let imported = catalogue::import(&pool, &rows, dry_run)
+                .await
+                .expect("Unable to import catalogue");
+            if dry_run {
+                println!("{} skills would be imported (dry run)", imported);
+            } else {
+                println!("{} skills imported", imported);
+            }
+        }
+        Command::Export { format, output } => {
+            let rows = catalogue::export(&pool)
+                .await
+                .expect("Unable to export catalogue");
+            let text = catalogue::render(&rows, format).expect("Unable to render catalogue");
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, text).expect("Unable to write catalogue file")
+                }
+                None => print!("{}", text),
+            }
+        }
+    }
+}
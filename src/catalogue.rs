@@ -0,0 +1,353 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+/// One global skill in an imported or exported catalogue.
+/// Steps of an acronym skill name the acronym as their parent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogueRow {
+    pub name: String,
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CatalogueFormat {
+    Yaml,
+    Json,
+    Csv,
+}
+
+impl FromStr for CatalogueFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!(
+                "{} is not a supported catalogue format. Use 'yaml', 'json' or 'csv'.",
+                other
+            )),
+        }
+    }
+}
+
+impl CatalogueFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Yaml => "application/yaml",
+            Self::Json => "application/json",
+            Self::Csv => "text/csv",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// A problem with the catalogue, pointing at the line the offending skill starts on.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct CatalogueError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl CatalogueError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses a catalogue, pairing each row with the line it starts on.
+pub fn parse(
+    text: &str,
+    format: CatalogueFormat,
+) -> Result<Vec<(usize, CatalogueRow)>, CatalogueError> {
+    match format {
+        CatalogueFormat::Json => {
+            let rows: Vec<CatalogueRow> = serde_json::from_str(text)
+                .map_err(|e| CatalogueError::new(e.line(), e.to_string()))?;
+            Ok(json_item_lines(text).into_iter().zip(rows).collect())
+        }
+        CatalogueFormat::Yaml => {
+            let rows: Vec<CatalogueRow> = serde_yaml::from_str(text).map_err(|e| {
+                let line = e.location().map(|location| location.line()).unwrap_or(1);
+                CatalogueError::new(line, e.to_string())
+            })?;
+            Ok(yaml_item_lines(text)?.into_iter().zip(rows).collect())
+        }
+        CatalogueFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(text.as_bytes());
+            let csv_error = |e: csv::Error| {
+                let line = e.position().map(|position| position.line()).unwrap_or(1);
+                CatalogueError::new(line as usize, e.to_string())
+            };
+            let headers = reader.headers().map_err(csv_error)?.clone();
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                let record = record.map_err(csv_error)?;
+                // Quoted fields can span lines, so ask the reader where the record began
+                let line = record
+                    .position()
+                    .map(|position| position.line())
+                    .unwrap_or(1) as usize;
+                let row: CatalogueRow = record
+                    .deserialize(Some(&headers))
+                    .map_err(|e| CatalogueError::new(line, e.to_string()))?;
+                rows.push((line, row));
+            }
+            Ok(rows)
+        }
+    }
+}
+
+// Lines the items of a top level JSON array start on
+fn json_item_lines(text: &str) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut line = 1;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                if depth == 1 {
+                    lines.push(line);
+                }
+                depth += 1;
+            }
+            '}' | ']' => depth -= 1,
+            _ => (),
+        }
+    }
+    lines
+}
+
+#[derive(Default)]
+struct YamlItemLines {
+    depth: usize,
+    lines: Vec<usize>,
+}
+
+impl MarkedEventReceiver for YamlItemLines {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::SequenceStart(_) | Event::MappingStart(_) => {
+                if self.depth == 1 {
+                    self.lines.push(mark.line());
+                }
+                self.depth += 1;
+            }
+            Event::SequenceEnd | Event::MappingEnd => self.depth -= 1,
+            _ => (),
+        }
+    }
+}
+
+// Lines the items of a top level YAML sequence start on
+fn yaml_item_lines(text: &str) -> Result<Vec<usize>, CatalogueError> {
+    let mut receiver = YamlItemLines::default();
+    Parser::new(text.chars())
+        .load(&mut receiver, false)
+        .map_err(|e| CatalogueError::new(e.marker().line(), e.to_string()))?;
+    Ok(receiver.lines)
+}
+
+/// Checks rows against each other and against the categories and skills already stored.
+pub async fn validate(
+    pool: &PgPool,
+    rows: &[(usize, CatalogueRow)],
+) -> Result<Vec<CatalogueError>, sqlx::Error> {
+    let categories: HashSet<String> =
+        sqlx::query_as::<_, (String,)>("SELECT name FROM skill_categories")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(name,)| name)
+            .collect();
+    let stored_parents: HashSet<String> = sqlx::query_as::<_, (String,)>(
+        "SELECT name FROM skills WHERE user_id IS NULL AND parent_id IS NULL",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(name,)| name)
+    .collect();
+
+    let mut errors = Vec::new();
+    let mut seen: HashMap<(&str, Option<&str>), usize> = HashMap::new();
+    let parents_in_file: HashMap<&str, &CatalogueRow> = rows
+        .iter()
+        .filter(|(_, row)| row.parent.is_none())
+        .map(|(_, row)| (row.name.as_str(), row))
+        .collect();
+    for (line, row) in rows {
+        let line = *line;
+        if row.name.trim().is_empty() {
+            errors.push(CatalogueError::new(line, "name is required"));
+        }
+        if row.category.trim().is_empty() {
+            errors.push(CatalogueError::new(line, "category is required"));
+        } else if !categories.contains(&row.category) {
+            errors.push(CatalogueError::new(
+                line,
+                format!("unknown category '{}'", row.category),
+            ));
+        }
+        if let Some(parent) = &row.parent {
+            let parent_known =
+                parents_in_file.contains_key(parent.as_str()) || stored_parents.contains(parent);
+            if !parent_known {
+                errors.push(CatalogueError::new(
+                    line,
+                    format!("parent '{}' is not a skill in the catalogue", parent),
+                ));
+            }
+        }
+        let key = (row.name.as_str(), row.parent.as_deref());
+        match seen.get(&key) {
+            Some(first_line) => errors.push(CatalogueError::new(
+                line,
+                format!(
+                    "'{}' is already in the catalogue on line {}",
+                    row.name, first_line
+                ),
+            )),
+            None => {
+                seen.insert(key, line);
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// Upserts every row by its natural key, acronym skills before their steps.
+/// Importing a retired skill brings it back.
+#[tracing::instrument(name = "Importing skill catalogue", skip(pool, rows))]
+pub async fn import(
+    pool: &PgPool,
+    rows: &[(usize, CatalogueRow)],
+    dry_run: bool,
+) -> Result<usize, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let (top_level, steps): (Vec<_>, Vec<_>) =
+        rows.iter().partition(|(_, row)| row.parent.is_none());
+    for (line, row) in top_level {
+        sqlx::query(
+            r#"
+    INSERT INTO skills (name, category, description) VALUES ($1, $2, $3)
+    ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+    DO UPDATE SET category = EXCLUDED.category, description = EXCLUDED.description,
+    deleted_at = NULL
+    "#,
+        )
+        .bind(&row.name)
+        .bind(&row.category)
+        .bind(&row.description)
+        .execute(&mut transaction)
+        .await
+        .with_context(|| format!("Failed to import line {}", line))?;
+    }
+    for (line, row) in steps {
+        sqlx::query(
+            r#"
+    INSERT INTO skills (name, category, description, parent_id)
+    SELECT $1, $2, $3, id FROM skills WHERE name = $4 AND parent_id IS NULL AND user_id IS NULL
+    ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+    DO UPDATE SET category = EXCLUDED.category, description = EXCLUDED.description,
+    deleted_at = NULL
+    "#,
+        )
+        .bind(&row.name)
+        .bind(&row.category)
+        .bind(&row.description)
+        .bind(&row.parent)
+        .execute(&mut transaction)
+        .await
+        .with_context(|| format!("Failed to import line {}", line))?;
+    }
+    if dry_run {
+        transaction.rollback().await?;
+    } else {
+        transaction.commit().await?;
+    }
+    Ok(rows.len())
+}
+
+/// The global catalogue in card order, each acronym skill followed by its steps.
+#[tracing::instrument(name = "Exporting skill catalogue", skip(pool))]
+pub async fn export(pool: &PgPool) -> Result<Vec<CatalogueRow>, sqlx::Error> {
+    let query_statement = r#"
+    SELECT skills.name, skills.category, COALESCE(skills.description, '') AS description,
+    parents.name AS parent
+    FROM skills
+    LEFT JOIN skills AS parents ON parents.id = skills.parent_id
+    JOIN skill_categories ON skill_categories.name = COALESCE(parents.category, skills.category)
+    WHERE skills.user_id IS NULL AND skills.deleted_at IS NULL
+    ORDER BY skill_categories.sort_order, COALESCE(skills.parent_id, skills.id),
+    skills.parent_id NULLS FIRST, skills.id
+    "#;
+    let rows: Vec<(String, String, String, Option<String>)> = sqlx::query_as(query_statement)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    Ok(rows
+        .into_iter()
+        .map(|(name, category, description, parent)| CatalogueRow {
+            name,
+            category,
+            description,
+            parent,
+        })
+        .collect())
+}
+
+pub fn render(rows: &[CatalogueRow], format: CatalogueFormat) -> Result<String, anyhow::Error> {
+    match format {
+        CatalogueFormat::Json => Ok(serde_json::to_string_pretty(rows)?),
+        CatalogueFormat::Yaml => Ok(serde_yaml::to_string(rows)?),
+        CatalogueFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+    }
+}
@@ -1,9 +1,14 @@
 use std::{
     convert::{TryFrom, TryInto},
+    path::Path,
     str::FromStr,
+    sync::Arc,
 };
 
-use secrecy::Secret;
+use crate::login_throttle::LoginThrottle;
+use crate::mailer::{FileMailer, InMemoryMailer, LogMailer, Mailer};
+use crate::password_policy::{BreachedPasswords, PasswordPolicy};
+use secrecy::{ExposeSecret, Secret};
 use sqlx::{postgres, ConnectOptions, PgPool};
 
 #[derive(serde::Deserialize)]
@@ -18,6 +23,15 @@ pub struct Settings {
     pub application_port: u16,
     pub redis_uri: Secret<String>,
     pub hmac_secret: Secret<String>,
+    // Where the app is served from, for links in the emails it sends
+    pub base_url: String,
+    pub mailer: MailerSettings,
+    pub password_policy: PasswordPolicySettings,
+    pub login_throttle: LoginThrottleSettings,
+    pub skill_rating_scale: RatingScale,
+    pub intensity_rating_scale: RatingScale,
+    // Directories under seeds/ that run_seeds applies, in order
+    pub seed_sets: Vec<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -29,6 +43,69 @@ pub struct DatabaseSettings {
     pub database_name: String,
 }
 
+// Where the emails the app sends end up
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum MailerSettings {
+    Log,
+    File { directory: String },
+    Memory,
+}
+
+impl MailerSettings {
+    pub fn build(&self) -> Arc<dyn Mailer> {
+        match self {
+            MailerSettings::Log => Arc::new(LogMailer),
+            MailerSettings::File { directory } => Arc::new(FileMailer::new(directory)),
+            MailerSettings::Memory => Arc::new(InMemoryMailer::default()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct PasswordPolicySettings {
+    pub min_length: usize,
+    // Lowest acceptable strength score, from 0 to 4
+    pub min_score: u8,
+    // File of SHA-1 hashes of known-breached passwords
+    pub breached_passwords: String,
+}
+
+impl PasswordPolicySettings {
+    pub fn load(&self) -> Result<PasswordPolicy, anyhow::Error> {
+        Ok(PasswordPolicy {
+            min_length: self.min_length,
+            min_score: self.min_score,
+            breached: BreachedPasswords::load(Path::new(&self.breached_passwords))?,
+        })
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct LoginThrottleSettings {
+    // Failed logins allowed before each further failure locks the email or IP out
+    pub max_failures_per_email: u64,
+    pub max_failures_per_ip: u64,
+    // Failures are forgotten this long after the first one
+    pub window_seconds: u64,
+    // The first lockout's length, doubling with every failure after it
+    pub base_lockout_seconds: u64,
+    pub max_lockout_seconds: u64,
+}
+
+// Inclusive bounds for a rating a user can give on their diary card
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RatingScale {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl RatingScale {
+    pub fn contains(&self, rating: i32) -> bool {
+        (self.min..=self.max).contains(&rating)
+    }
+}
+
 // Possible environments for the app
 #[derive(Debug, Clone)]
 pub enum Environment {
@@ -41,6 +118,12 @@ pub struct AppData {
     pub env: Environment,
     pub db_name: String,
     pub pg_pool: sqlx::PgPool,
+    pub skill_rating_scale: RatingScale,
+    pub intensity_rating_scale: RatingScale,
+    pub base_url: String,
+    pub mailer: Arc<dyn Mailer>,
+    pub password_policy: Arc<PasswordPolicy>,
+    pub login_throttle: Arc<LoginThrottle>,
 }
 
 impl AppData {
@@ -61,6 +144,24 @@ impl AppData {
             db_name: setting.database.database_name.clone(),
             env,
             pg_pool,
+            skill_rating_scale: setting.skill_rating_scale.clone(),
+            intensity_rating_scale: setting.intensity_rating_scale.clone(),
+            base_url: setting.base_url.clone(),
+            mailer: setting.mailer.build(),
+            password_policy: Arc::new(
+                setting
+                    .password_policy
+                    .load()
+                    .expect("Failed to load the password policy"),
+            ),
+            login_throttle: Arc::new(
+                LoginThrottle::connect(
+                    setting.login_throttle.clone(),
+                    setting.redis_uri.expose_secret(),
+                    &setting.database.database_name,
+                )
+                .await,
+            ),
         }
     }
 }
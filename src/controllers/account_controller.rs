@@ -0,0 +1,144 @@
+use crate::configuration::AppData;
+use crate::controllers::authentication::AuthenticatedUser;
+use crate::controllers::credentials_controller::client_ip;
+use crate::controllers::{ErrorResponse, LoginForm, PasswordChangeForm, SettingsForm};
+use crate::models::{
+    change_password, get_email, validate_credentials, AuthError, PasswordResetToken, UnitOfWork,
+    UserSettings,
+};
+
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::web;
+use actix_web::{HttpRequest, HttpResponse};
+use secrecy::ExposeSecret;
+
+// Retrieves the user's settings
+pub async fn show_settings(
+    config: web::Data<AppData>,
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    match UserSettings::find(&config, &user.id()).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(settings)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+// Changes the settings given in the form, leaving the rest as they are
+pub async fn update_settings(
+    form: web::Json<SettingsForm>,
+    config: web::Data<AppData>,
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let current = match UserSettings::find(&config, &user_id).await {
+        Ok(settings) => settings,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let form = form.into_inner();
+    if let Some(timezone) = &form.timezone {
+        match UserSettings::is_known_timezone(&config, timezone).await {
+            Ok(true) => (),
+            Ok(false) => {
+                return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("{} is not a known timezone", timezone),
+                }))
+            }
+            Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+        }
+    }
+    let settings = match form.apply_to(current) {
+        Ok(settings) => settings,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e })),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let settings = match settings.update(&mut unit_of_work, &user_id).await {
+        Ok(settings) => settings,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+// Changes the user's password once they've confirmed their current one.
+// Wrong current passwords count towards the same lockout as failed logins.
+pub async fn update_password(
+    req: HttpRequest,
+    form: web::Json<PasswordChangeForm>,
+    config: web::Data<AppData>,
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let form = form.into_inner();
+    let email = match get_email(user_id, &config).await {
+        Ok(email) => email,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let ip = client_ip(&req);
+    if let Some(retry_after) = config.login_throttle.retry_after(&email, &ip).await {
+        return Ok(too_many_attempts(retry_after));
+    }
+    let login_data = LoginForm {
+        email: email.clone(),
+        password: form.current_password,
+    };
+    match validate_credentials(&config, login_data).await {
+        Ok(id) if id == user_id => config.login_throttle.record_success(&email).await,
+        Ok(_) | Err(AuthError::InvalidCredentials(_)) => {
+            let lockout = config.login_throttle.record_failure(&email, &ip).await;
+            if let Some(retry_after) = lockout {
+                return Ok(too_many_attempts(retry_after));
+            }
+            return Ok(HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "current_password is incorrect".to_string(),
+            }));
+        }
+        Err(AuthError::UnexpectedError(_)) => {
+            return Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+    // Only someone who knows the current password gets feedback on the new one
+    let errors = config
+        .password_policy
+        .check(form.new_password.expose_secret(), &[&email]);
+    if !errors.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("new_password {}", errors.join("; ")),
+        }));
+    }
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if change_password(user_id, form.new_password, &mut unit_of_work)
+        .await
+        .is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if PasswordResetToken::revoke_all(&mut unit_of_work, &user_id)
+        .await
+        .is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn too_many_attempts(retry_after: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((RETRY_AFTER, retry_after))
+        .json(ErrorResponse {
+            error: format!(
+                "Too many failed password attempts, try again in {} seconds",
+                retry_after
+            ),
+        })
+}
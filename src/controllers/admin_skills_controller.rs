@@ -0,0 +1,138 @@
+use crate::configuration::AppData;
+use crate::controllers::authentication::AdminUser;
+use crate::controllers::skills_controller::unknown_category;
+use crate::controllers::{ErrorResponse, SkillCategoryForm, SkillForm};
+use crate::models::{is_foreign_key_violation, is_unique_violation, Record, Skill, UnitOfWork};
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+//Creates a skill in the global catalogue
+pub async fn create(
+    form: web::Json<SkillForm>,
+    config: web::Data<AppData>,
+    _admin: AdminUser,
+) -> actix_web::Result<HttpResponse> {
+    let skill_form = form.into_inner();
+    if let Err(e) = skill_form.validate() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
+    let skill = Skill {
+        id: 0,
+        name: skill_form.name,
+        category: skill_form.category,
+        description: skill_form.description,
+        user_id: None,
+        parent_id: None,
+    };
+
+    match skill.save(&config).await {
+        Ok(skill) => Ok(HttpResponse::Created().json(skill)),
+        Err(e) if is_foreign_key_violation(&e) => Ok(unknown_category()),
+        Err(e) if is_unique_violation(&e) => Ok(duplicate_name()),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+// Updates the name, category and description of a global skill
+pub async fn update(
+    form: web::Json<SkillForm>,
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    _admin: AdminUser,
+) -> actix_web::Result<HttpResponse> {
+    let skill_form = form.into_inner();
+    if let Err(e) = skill_form.validate() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
+    let skill_id: i32 = match params.0.parse() {
+        Ok(skill_id) => skill_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let skill = Skill::update_global(
+        &mut unit_of_work,
+        skill_id,
+        &skill_form.name,
+        &skill_form.category,
+        &skill_form.description,
+    );
+    let skill = match skill.await {
+        Ok(skill) => skill,
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(e) if is_foreign_key_violation(&e) => return Ok(unknown_category()),
+        Err(e) if is_unique_violation(&e) => return Ok(duplicate_name()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Ok().json(skill))
+}
+
+// Moves a global skill to another category
+pub async fn recategorize(
+    form: web::Json<SkillCategoryForm>,
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    _admin: AdminUser,
+) -> actix_web::Result<HttpResponse> {
+    let category = form.into_inner().category;
+    if category.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "A skill needs a category".to_string(),
+        }));
+    }
+    let skill_id: i32 = match params.0.parse() {
+        Ok(skill_id) => skill_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let skill = match Skill::recategorize(&mut unit_of_work, skill_id, &category).await {
+        Ok(skill) => skill,
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(e) if is_foreign_key_violation(&e) => return Ok(unknown_category()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Ok().json(skill))
+}
+
+// Retires a global skill; diary entries that used it keep their links
+pub async fn delete(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    _admin: AdminUser,
+) -> actix_web::Result<HttpResponse> {
+    let skill_id: i32 = match params.0.parse() {
+        Ok(skill_id) => skill_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    match Skill::soft_delete(&mut unit_of_work, skill_id).await {
+        Ok(_) => (),
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn duplicate_name() -> HttpResponse {
+    HttpResponse::Conflict().json(ErrorResponse {
+        error: "A global skill with that name already exists".to_string(),
+    })
+}
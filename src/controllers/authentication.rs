@@ -0,0 +1,131 @@
+use crate::configuration::AppData;
+use crate::controllers::ErrorResponse;
+use crate::models::{is_admin, is_email_verified};
+use actix_session::SessionExt;
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthenticationError {
+    #[error("You must be logged in to access this resource")]
+    NotLoggedIn,
+    #[error("You do not have permission to access this resource")]
+    Forbidden,
+    #[error("Verify your email address to access this resource")]
+    Unverified,
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for AuthenticationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticationError::NotLoggedIn => StatusCode::UNAUTHORIZED,
+            AuthenticationError::Forbidden | AuthenticationError::Unverified => {
+                StatusCode::FORBIDDEN
+            }
+            AuthenticationError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            error: self.to_string(),
+        })
+    }
+}
+
+/// The id of the user attached to the current session.
+/// Handlers taking this extractor reject anonymous requests with a 401.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthenticatedUser(i32);
+
+impl AuthenticatedUser {
+    pub fn id(&self) -> i32 {
+        self.0
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthenticationError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user_id = req
+            .get_session()
+            .get::<i32>("user_id")
+            .map_err(|e| AuthenticationError::UnexpectedError(e.into()));
+        ready(match user_id {
+            Ok(Some(user_id)) => Ok(AuthenticatedUser(user_id)),
+            Ok(None) => Err(AuthenticationError::NotLoggedIn),
+            Err(e) => Err(e),
+        })
+    }
+}
+
+/// The id of a logged in user who has verified their email address.
+/// Anonymous requests get a 401, unverified users a 403.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedUser(i32);
+
+impl VerifiedUser {
+    pub fn id(&self) -> i32 {
+        self.0
+    }
+}
+
+impl FromRequest for VerifiedUser {
+    type Error = AuthenticationError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload).into_inner();
+        let config = req.app_data::<web::Data<AppData>>().cloned();
+        Box::pin(async move {
+            let user_id = user?.id();
+            let config = config.ok_or_else(|| {
+                AuthenticationError::UnexpectedError(anyhow::anyhow!("AppData is not registered"))
+            })?;
+            match is_email_verified(user_id, &config).await {
+                Ok(true) => Ok(VerifiedUser(user_id)),
+                Ok(false) => Err(AuthenticationError::Unverified),
+                Err(e) => Err(AuthenticationError::UnexpectedError(e)),
+            }
+        })
+    }
+}
+
+/// The id of a logged in user with the admin role.
+/// Anonymous requests get a 401, other users a 403.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminUser(i32);
+
+impl AdminUser {
+    pub fn id(&self) -> i32 {
+        self.0
+    }
+}
+
+impl FromRequest for AdminUser {
+    type Error = AuthenticationError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload).into_inner();
+        let config = req.app_data::<web::Data<AppData>>().cloned();
+        Box::pin(async move {
+            let user_id = user?.id();
+            let config = config.ok_or_else(|| {
+                AuthenticationError::UnexpectedError(anyhow::anyhow!("AppData is not registered"))
+            })?;
+            match is_admin(user_id, &config).await {
+                Ok(true) => Ok(AdminUser(user_id)),
+                Ok(false) => Err(AuthenticationError::Forbidden),
+                Err(e) => Err(AuthenticationError::UnexpectedError(e)),
+            }
+        })
+    }
+}
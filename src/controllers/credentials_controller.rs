@@ -1,15 +1,25 @@
 use crate::configuration::AppData;
-use crate::controllers::LoginForm;
-use crate::models::{create_user, get_name, validate_credentials, AuthError};
+use crate::controllers::authentication::AuthenticatedUser;
+use crate::controllers::{
+    ErrorResponse, ForgotPasswordForm, LoginForm, PasswordResetForm, VerifyEmailQuery,
+};
+use crate::mailer::Email;
+use crate::models::{
+    change_password, create_user, find_user_id_by_email, get_email, get_name, is_email_verified,
+    is_unique_violation, validate_credentials, AuthError, EmailVerification, PasswordResetToken,
+    Streaks, UnitOfWork, RESET_TOKEN_LIFETIME_MINUTES, VERIFICATION_LINK_LIFETIME_HOURS,
+};
+use crate::HmacSecret;
 use actix_session::Session;
 use actix_web::error::InternalError;
-use actix_web::http::header::LOCATION;
+use actix_web::http::header::{LOCATION, RETRY_AFTER};
 use actix_web::http::StatusCode;
-use actix_web::HttpResponse;
 use actix_web::{web, ResponseError};
-use validator::validate_email;
+use actix_web::{HttpRequest, HttpResponse};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
 
-use super::SignupForm;
+use super::{FieldErrors, SignupForm};
 
 // Return an opaque 500 while preserving the error's root cause for logging.
 fn e500<T>(e: T) -> actix_web::Error
@@ -23,19 +33,40 @@ where
 pub enum LoginError {
     #[error("Authentication failed")]
     AuthError(#[source] anyhow::Error),
+    #[error("Too many failed login attempts, try again in {retry_after} seconds")]
+    TooManyAttempts { retry_after: u64 },
     #[error("Something went wrong")]
     UnexpectedError(#[from] anyhow::Error),
 }
 
+// The address failed logins are counted against. The peer address rather than
+// X-Forwarded-For, which clients can set to anything.
+pub fn client_ip(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|address| address.ip().to_string())
+        .unwrap_or_default()
+}
+
+// Failed logins are counted per email and per client address; once either has
+// failed too often it is refused with a 429 until its lockout runs out.
 pub async fn login(
+    req: HttpRequest,
     data: web::Json<LoginForm>,
     config: web::Data<AppData>,
     session: Session,
 ) -> Result<HttpResponse, InternalError<LoginError>> {
     let login_data = data.into_inner();
+    let email = login_data.email.clone();
+    let ip = client_ip(&req);
+
+    // Checked before the password, so a locked out client costs no argon2 work
+    if let Some(retry_after) = config.login_throttle.retry_after(&email, &ip).await {
+        return Err(too_many_attempts(retry_after));
+    }
 
     match validate_credentials(&config, login_data).await {
         Ok(user_id) => {
+            config.login_throttle.record_success(&email).await;
             session.renew();
             session
                 .insert("user_id", user_id)
@@ -44,7 +75,14 @@ pub async fn login(
         }
         Err(e) => {
             let e = match e {
-                AuthError::InvalidCredentials(_) => LoginError::AuthError(e.into()),
+                AuthError::InvalidCredentials(_) => {
+                    // The failure that starts a lockout already answers with it
+                    let lockout = config.login_throttle.record_failure(&email, &ip).await;
+                    if let Some(retry_after) = lockout {
+                        return Err(too_many_attempts(retry_after));
+                    }
+                    LoginError::AuthError(e.into())
+                }
                 AuthError::UnexpectedError(_) => LoginError::UnexpectedError(e.into()),
             };
             Err(login_redirect(e))
@@ -66,6 +104,31 @@ pub async fn session_name(
     Ok(HttpResponse::Ok().json(name))
 }
 
+// The logged in user with their logging streaks, for the app's header
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WhoAmI {
+    pub id: i32,
+    pub name: String,
+    pub email_verified: bool,
+    pub streaks: Streaks,
+}
+
+pub async fn whoami(
+    config: web::Data<AppData>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = user.id();
+    let name = get_name(id, &config).await.map_err(e500)?;
+    let email_verified = is_email_verified(id, &config).await.map_err(e500)?;
+    let streaks = Streaks::for_user(&config, &id).await.map_err(e500)?;
+    Ok(HttpResponse::Ok().json(WhoAmI {
+        id,
+        name,
+        email_verified,
+        streaks,
+    }))
+}
+
 pub async fn logout(session: Session) -> Result<HttpResponse, actix_web::Error> {
     if session.get::<i32>("user_id").map_err(e500)?.is_none() {
         Ok(HttpResponse::BadRequest().finish())
@@ -82,27 +145,260 @@ fn login_redirect(e: LoginError) -> InternalError<LoginError> {
     InternalError::from_response(e, response)
 }
 
+fn too_many_attempts(retry_after: u64) -> InternalError<LoginError> {
+    let e = LoginError::TooManyAttempts { retry_after };
+    let response = HttpResponse::TooManyRequests()
+        .insert_header((RETRY_AFTER, retry_after))
+        .json(ErrorResponse {
+            error: e.to_string(),
+        });
+    InternalError::from_response(e, response)
+}
+
 impl ResponseError for LoginError {
     fn status_code(&self) -> StatusCode {
         match self {
             LoginError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             LoginError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            LoginError::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum SignupError {
+    #[error("Signup form is invalid")]
+    ValidationError(FieldErrors),
+    #[error("An account with this email already exists")]
+    DuplicateEmail,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+// The body of a failed signup, with what is wrong with each field
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignupErrorResponse {
+    pub error: String,
+    #[serde(default, skip_serializing_if = "FieldErrors::is_empty")]
+    pub fields: FieldErrors,
+}
+
+impl ResponseError for SignupError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SignupError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            SignupError::DuplicateEmail => StatusCode::CONFLICT,
+            SignupError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let fields = match self {
+            SignupError::ValidationError(fields) => fields.clone(),
+            SignupError::DuplicateEmail => FieldErrors::from([(
+                "email".to_string(),
+                vec!["is already registered".to_string()],
+            )]),
+            SignupError::UnexpectedError(_) => FieldErrors::new(),
+        };
+        HttpResponse::build(self.status_code()).json(SignupErrorResponse {
+            error: self.to_string(),
+            fields,
+        })
+    }
+}
+
+// New accounts start unverified and are emailed a link to verify their address
 pub async fn signup(
     data: web::Json<SignupForm>,
     config: web::Data<AppData>,
-) -> actix_web::Result<HttpResponse> {
+    hmac_secret: web::Data<HmacSecret>,
+) -> Result<HttpResponse, SignupError> {
     let signup_data = data.into_inner();
+    let field_errors = signup_data.field_errors(&config.password_policy);
+    if !field_errors.is_empty() {
+        return Err(SignupError::ValidationError(field_errors));
+    }
 
-    if validate_email(&signup_data.email) {
-        match create_user(signup_data, &config).await {
-            Ok(_) => Ok(HttpResponse::Ok().finish()),
-            Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    let email = signup_data.email.trim().to_string();
+    let user_id = create_user(signup_data, &config).await.map_err(|e| {
+        match e.downcast_ref::<sqlx::Error>() {
+            Some(sqlx_error) if is_unique_violation(sqlx_error) => SignupError::DuplicateEmail,
+            _ => SignupError::UnexpectedError(e),
         }
-    } else {
-        return Ok(HttpResponse::BadRequest().json(&signup_data.email));
+    })?;
+    // The account exists either way; the user can ask for the link again
+    if let Err(e) = send_verification_email(&config, &hmac_secret, user_id, email).await {
+        tracing::error!("Failed to send verification email: {:?}", e);
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+async fn send_verification_email(
+    config: &AppData,
+    hmac_secret: &HmacSecret,
+    user_id: i32,
+    email: String,
+) -> Result<(), anyhow::Error> {
+    let token = EmailVerification::new(user_id).token(&email, &hmac_secret.0);
+    let verification_email = Email {
+        to: email,
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Follow this link to verify your email address:\n\n{}/verify_email?token={}\n\n\
+             The link expires in {} hours.",
+            config.base_url, token, VERIFICATION_LINK_LIFETIME_HOURS
+        ),
+    };
+    config.mailer.send(verification_email).await
+}
+
+// Verifies the address a link from a verification email was sent to
+pub async fn verify_email(
+    query: web::Query<VerifyEmailQuery>,
+    config: web::Data<AppData>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = match EmailVerification::check(&config, &query.token, &hmac_secret.0).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "verification link is invalid or has expired".to_string(),
+            }))
+        }
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if EmailVerification::mark_verified(&mut unit_of_work, &user_id)
+        .await
+        .is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Sends the logged in user a new verification link
+pub async fn resend_verification(
+    config: web::Data<AppData>,
+    hmac_secret: web::Data<HmacSecret>,
+    user: AuthenticatedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    match is_email_verified(user_id, &config).await {
+        Ok(false) => (),
+        Ok(true) => {
+            return Ok(HttpResponse::Conflict().json(ErrorResponse {
+                error: "email address is already verified".to_string(),
+            }))
+        }
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    }
+    let email = match get_email(user_id, &config).await {
+        Ok(email) => email,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if let Err(e) = send_verification_email(&config, &hmac_secret, user_id, email).await {
+        tracing::error!("Failed to send verification email: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+// Emails a reset link when the address belongs to a user. The response is the
+// same either way, so the endpoint can't be used to find out who has an account.
+pub async fn forgot_password(
+    form: web::Json<ForgotPasswordForm>,
+    config: web::Data<AppData>,
+) -> actix_web::Result<HttpResponse> {
+    let email = form.into_inner().email;
+    let user_id = match find_user_id_by_email(&email, &config).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return Ok(HttpResponse::Accepted().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let token = match PasswordResetToken::issue(&mut unit_of_work, &user_id).await {
+        Ok(token) => token,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    let reset_email = Email {
+        to: email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Follow this link to choose a new password:\n\n{}/reset_password?token={}\n\n\
+             The link works once and expires in {} minutes.",
+            config.base_url,
+            token.expose_secret(),
+            RESET_TOKEN_LIFETIME_MINUTES
+        ),
+    };
+    if let Err(e) = config.mailer.send(reset_email).await {
+        tracing::error!("Failed to send password reset email: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+// Sets a new password with a token from a forgot password email
+pub async fn reset_password(
+    form: web::Json<PasswordResetForm>,
+    config: web::Data<AppData>,
+) -> actix_web::Result<HttpResponse> {
+    let form = form.into_inner();
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let user_id = match PasswordResetToken::redeem(&mut unit_of_work, &form.token).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "reset token is invalid, used or expired".to_string(),
+            }))
+        }
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    // Checked once the token is known to be good, so the user's details can be used.
+    // Rejecting the password rolls back the redemption, leaving the token usable.
+    let email = match get_email(user_id, &config).await {
+        Ok(email) => email,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let errors = config
+        .password_policy
+        .check(form.password.expose_secret(), &[&email]);
+    if !errors.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("password {}", errors.join("; ")),
+        }));
+    }
+    if change_password(user_id, form.password, &mut unit_of_work)
+        .await
+        .is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if PasswordResetToken::revoke_all(&mut unit_of_work, &user_id)
+        .await
+        .is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
     }
+    Ok(HttpResponse::NoContent().finish())
 }
@@ -0,0 +1,32 @@
+use crate::configuration::AppData;
+use crate::controllers::authentication::VerifiedUser;
+use crate::controllers::ErrorResponse;
+use crate::models::{parse_iso_week, DiaryCard, UserSettings};
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+// Retrieves the user's diary card for an ISO week, starting on their week start day
+pub async fn show(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let iso_monday = match parse_iso_week(&params.0) {
+        Some(iso_monday) => iso_monday,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Weeks are written like 2022-W06".to_string(),
+            }))
+        }
+    };
+    let settings = match UserSettings::find(&config, &user_id).await {
+        Ok(settings) => settings,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    match DiaryCard::for_week(&config, iso_monday, settings.week_start_day(), &user_id).await {
+        Ok(diary_card) => Ok(HttpResponse::Ok().json(diary_card)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
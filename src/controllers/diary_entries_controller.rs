@@ -1,151 +1,391 @@
 use crate::configuration::AppData;
-use crate::controllers::DiaryForm;
+use crate::controllers::authentication::VerifiedUser;
+use crate::controllers::{includes_skills, DiaryForm, ErrorResponse, IncludeQuery, Page};
 use crate::models::{
-    save_from_form, update_diary_entry, DateRangeRequest, DiaryEntry, DiaryEntrySkills, Record,
-    Skill,
+    is_unique_violation, save_from_form, update_diary_entry, upsert_from_form, DiaryEntry,
+    DiaryEntryEmotion, DiaryEntryQuery, DiaryEntrySkills, DiaryEntryUrge, Emotion, RatedSkill,
+    Skill, UnitOfWork, Urge,
 };
 
-use actix_session::Session;
 use actix_web::web;
 use actix_web::HttpResponse;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// A diary entry together with the emotion and urge intensities recorded on it
+#[derive(Serialize, Debug)]
+pub struct DiaryEntryDetails {
+    #[serde(flatten)]
+    pub entry: DiaryEntry,
+    pub emotions: Vec<DiaryEntryEmotion>,
+    pub urges: Vec<DiaryEntryUrge>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skills: Option<Vec<RatedSkill>>,
+}
+
+// A diary entry with the skills rated on it, listed by index with ?include=skills
+#[derive(Serialize, Debug)]
+pub struct DiaryEntryWithSkills {
+    #[serde(flatten)]
+    pub entry: DiaryEntry,
+    pub skills: Vec<RatedSkill>,
+}
+
+// Saves the skill ratings and steps, then the emotion and urge intensities, from the form
+async fn save_ratings(
+    diary_form: &DiaryForm,
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<(), sqlx::Error> {
+    let skills = find_form_skills(diary_form, diary_entry, unit_of_work).await?;
+    save_skill_ratings(diary_form, &skills, diary_entry, unit_of_work).await?;
+    save_intensities(diary_form, diary_entry, unit_of_work).await
+}
+
+// Saves the form over everything rated on the diary entry before. The skills are
+// looked up first, while the entry still links the retired skills it keeps.
+async fn replace_ratings(
+    diary_form: &DiaryForm,
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<(), sqlx::Error> {
+    let skills = find_form_skills(diary_form, diary_entry, unit_of_work).await?;
+    clear_ratings(diary_entry, unit_of_work).await?;
+    save_skill_ratings(diary_form, &skills, diary_entry, unit_of_work).await?;
+    save_intensities(diary_form, diary_entry, unit_of_work).await
+}
+
+// The skills in the form that can be rated on the diary entry
+async fn find_form_skills(
+    diary_form: &DiaryForm,
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<Vec<Skill>, sqlx::Error> {
+    let skill_ids = diary_form.skill_ids();
+    if skill_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    Skill::find_by_ids_for_entry(unit_of_work, &skill_ids, diary_entry).await
+}
+
+// Saves the form's rating and steps for each of the skills
+async fn save_skill_ratings(
+    diary_form: &DiaryForm,
+    skills: &[Skill],
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<(), sqlx::Error> {
+    for skill in skills {
+        let rating = diary_form.rating_for(skill.id).unwrap_or_default();
+        DiaryEntrySkills::save_diary_entry_skill(
+            unit_of_work,
+            skill,
+            rating,
+            diary_form.steps_for(skill.id),
+            diary_entry,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Removes everything rated on the diary entry so the form can be saved over it
+async fn clear_ratings(
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<(), sqlx::Error> {
+    DiaryEntrySkills::delete(unit_of_work, diary_entry).await?;
+    DiaryEntryEmotion::delete(unit_of_work, diary_entry).await?;
+    DiaryEntryUrge::delete(unit_of_work, diary_entry).await?;
+    Ok(())
+}
+
+// Saves the emotion and urge intensities from the form against the diary entry
+async fn save_intensities(
+    diary_form: &DiaryForm,
+    diary_entry: &DiaryEntry,
+    unit_of_work: &mut UnitOfWork<'_>,
+) -> Result<(), sqlx::Error> {
+    let emotion_ids = diary_form.emotion_ids();
+    if !emotion_ids.is_empty() {
+        for emotion in Emotion::find_by_ids(unit_of_work, &emotion_ids).await? {
+            let intensity = diary_form
+                .intensity_for_emotion(emotion.id)
+                .unwrap_or_default();
+            DiaryEntryEmotion::save(unit_of_work, &emotion, intensity, diary_entry).await?;
+        }
+    }
+    let urge_ids = diary_form.urge_ids();
+    if !urge_ids.is_empty() {
+        for urge in Urge::find_by_ids(unit_of_work, &urge_ids).await? {
+            let intensity = diary_form.intensity_for_urge(urge.id).unwrap_or_default();
+            DiaryEntryUrge::save(unit_of_work, &urge, intensity, diary_entry).await?;
+        }
+    }
+    Ok(())
+}
 
 //Creates a new diary entry from an Json
 pub async fn create(
     form: web::Json<DiaryForm>,
     config: web::Data<AppData>,
-    session: Session,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
-    let user_id = match session.get::<i32>("user_id") {
-        Ok(user_id) => match user_id {
-            Some(user_id) => user_id,
-            None => return Ok(HttpResponse::InternalServerError().finish()),
-        },
-        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
-    };
+    let user_id = user.id();
 
     let diary_form = form.into_inner();
-    let diary_entry =
-        match save_from_form(&diary_form.entry_date, &diary_form.notes, &config, &user_id).await {
-            Ok(entry) => entry,
-            Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
-        };
-    let skills_list = diary_form.skill_ids;
-    if skills_list.is_empty() {
-        return Ok(HttpResponse::Created().json(&diary_entry));
+    if let Err(e) = diary_form
+        .validate_ratings(&config.skill_rating_scale)
+        .and_then(|_| diary_form.validate_intensities(&config.intensity_rating_scale))
+    {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
-
-    let skill_records = Skill::find_by_ids(&config, &skills_list);
-    let skills = match skill_records.await {
-        Ok(skills) => skills,
+    let diary_entry = match save_from_form(
+        &diary_form.entry_date,
+        &diary_form.notes,
+        &mut unit_of_work,
+        &user_id,
+    )
+    .await
+    {
+        Ok(entry) => entry,
+        Err(e) if is_unique_violation(&e) => return Ok(entry_exists()),
         Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
-    for skill in skills {
-        let diary_entry_skills =
-            DiaryEntrySkills::save_diary_entry_skill(&config, &skill, &diary_entry);
-        if diary_entry_skills.await.is_err() {
-            return Ok(HttpResponse::InternalServerError().finish());
-        }
+    if save_ratings(&diary_form, &diary_entry, &mut unit_of_work)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
     }
     Ok(HttpResponse::Created().json(&diary_entry))
 }
 
-// Updates diary entry and diary_entry_skills for a date
+// Updates diary entry, diary_entry_skills and emotion/urge intensities for an entry
 pub async fn update(
     form: web::Json<DiaryForm>,
     params: web::Path<(String,)>,
     config: web::Data<AppData>,
-    session: Session,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
     let diary_form = form.into_inner();
-    let user_id = match session.get::<i32>("user_id") {
-        Ok(user_id) => match user_id {
-            Some(user_id) => user_id,
-            None => return Ok(HttpResponse::InternalServerError().finish()),
-        },
-        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
-    };
+    let user_id = user.id();
+    if let Err(e) = diary_form
+        .validate_ratings(&config.skill_rating_scale)
+        .and_then(|_| diary_form.validate_intensities(&config.intensity_rating_scale))
+    {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
 
     let id = &params.0;
-    let entry_id: i32 = id.parse().unwrap();
-    let entry = DiaryEntry::find_by_id(&config, entry_id);
+    let entry_id: i32 = match id.parse() {
+        Ok(entry_id) => entry_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let entry = DiaryEntry::find_by_id(&config, entry_id, &user_id);
     let diary_entry = match entry.await {
         Ok(diary_entry) => diary_entry,
-        Err(_) => return Ok(HttpResponse::NotFound().finish()),
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
-    let updated_notes = diary_form.notes;
+    let updated_notes = &diary_form.notes;
     let updated_entry =
-        match update_diary_entry(&diary_entry.id, &updated_notes, &config, &user_id).await {
+        match update_diary_entry(&diary_entry.id, updated_notes, &mut unit_of_work, &user_id).await
+        {
             Ok(entry) => entry,
             Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
         };
-    let delete_diary_entry_skills = DiaryEntrySkills::delete(&config, &updated_entry);
-    match delete_diary_entry_skills.await {
-        Ok(deleted_diary_entry_skills) => deleted_diary_entry_skills,
+    if replace_ratings(&diary_form, &updated_entry, &mut unit_of_work)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Ok().json(&updated_entry))
+}
+
+// Creates or replaces the user's diary entry for a date, so a day can be saved repeatedly
+pub async fn upsert(
+    form: web::Json<DiaryForm>,
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let diary_form = form.into_inner();
+    let user_id = user.id();
+    let diary_entry_date: sqlx::types::chrono::NaiveDate = match params.0.parse() {
+        Ok(entry_date) => entry_date,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    if diary_form.entry_date.naive_utc().date() != diary_entry_date {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "The entry_date does not match the date in the path".to_string(),
+        }));
+    }
+    if let Err(e) = diary_form
+        .validate_ratings(&config.skill_rating_scale)
+        .and_then(|_| diary_form.validate_intensities(&config.intensity_rating_scale))
+    {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
         Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
-    let skills_id_list = diary_form.skill_ids;
-    if skills_id_list.is_empty() {
-        return Ok(HttpResponse::Created().json(&diary_entry));
+    let (diary_entry, created) = match upsert_from_form(
+        &diary_form.entry_date,
+        &diary_form.notes,
+        &mut unit_of_work,
+        &user_id,
+    )
+    .await
+    {
+        Ok(upserted) => upserted,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
+    if replace_ratings(&diary_form, &diary_entry, &mut unit_of_work)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    if created {
+        Ok(HttpResponse::Created().json(&diary_entry))
+    } else {
+        Ok(HttpResponse::Ok().json(&diary_entry))
+    }
+}
 
-    let skill_records = Skill::find_by_ids(&config, &skills_id_list);
-    let skills = match skill_records.await {
-        Ok(skills) => skills,
-        Err(_) => return Ok(HttpResponse::NotFound().finish()),
+// Soft deletes a diary entry, which can be restored within the restore window
+pub async fn delete(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let entry_id: i32 = match params.0.parse() {
+        Ok(entry_id) => entry_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
     };
-    for skill in skills {
-        let diary_entry_skills =
-            DiaryEntrySkills::save_diary_entry_skill(&config, &skill, &diary_entry);
-        if diary_entry_skills.await.is_err() {
-            return Ok(HttpResponse::InternalServerError().finish());
-        }
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    match DiaryEntry::soft_delete(&mut unit_of_work, entry_id, &user_id).await {
+        Ok(_) => (),
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if DiaryEntry::purge_expired(&mut unit_of_work, &user_id)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
     }
-    Ok(HttpResponse::Created().json(&diary_entry))
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Restores a diary entry deleted within the restore window
+pub async fn restore(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let entry_id: i32 = match params.0.parse() {
+        Ok(entry_id) => entry_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let diary_entry = match DiaryEntry::restore(&mut unit_of_work, entry_id, &user_id).await {
+        Ok(diary_entry) => diary_entry,
+        Err(e) if is_unique_violation(&e) => return Ok(entry_exists()),
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if unit_of_work.commit().await.is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Ok().json(&diary_entry))
 }
 
-// Retrieves diary entry by date
+// Retrieves diary entry by date along with its emotion and urge intensities,
+// and its rated skills with ?include=skills
 pub async fn show(
     params: web::Path<(String,)>,
+    query: web::Query<IncludeQuery>,
     config: web::Data<AppData>,
-    session: Session,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
+    let include_skills = match includes_skills(query.include.as_deref()) {
+        Ok(include_skills) => include_skills,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e })),
+    };
     let date = &params.0;
     let diary_entry_date: sqlx::types::chrono::NaiveDate = match date.parse() {
         Ok(entry_date) => entry_date,
         Err(_) => return Ok(HttpResponse::BadRequest().finish()),
     };
-    let user_id = match session.get::<i32>("user_id") {
-        Ok(user_id) => match user_id {
-            Some(user_id) => user_id,
-            None => return Ok(HttpResponse::InternalServerError().finish()),
-        },
+    let user_id = user.id();
+
+    let entry = match DiaryEntry::find_by_date(&config, diary_entry_date, &user_id).await {
+        Ok(entry) => entry,
+        Err(_) => return Ok(HttpResponse::NotFound().finish()),
+    };
+    let emotions = match DiaryEntryEmotion::find_by_diary_entry(&config, entry.id, &user_id).await {
+        Ok(emotions) => emotions,
         Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
-
-    match DiaryEntry::find_by_date(&config, diary_entry_date, &user_id).await {
-        Ok(entry) => Ok(HttpResponse::Ok().json(entry)),
-        Err(_) => Ok(HttpResponse::NotFound().finish()),
-    }
+    let urges = match DiaryEntryUrge::find_by_diary_entry(&config, entry.id, &user_id).await {
+        Ok(urges) => urges,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let skills = if include_skills {
+        match RatedSkill::find_by_diary_entries(&config, &[entry.id], &user_id).await {
+            Ok(skills) => Some(skills),
+            Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+        }
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok().json(DiaryEntryDetails {
+        entry,
+        emotions,
+        urges,
+        skills,
+    }))
 }
 
 //Retrieves all diary_entry_skills for a particular diary_entry date and user
 pub async fn show_skills(
     params: web::Path<(String,)>,
     config: web::Data<AppData>,
-    session: Session,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
     let date = &params.0;
     let diary_entry_date: sqlx::types::chrono::NaiveDate = match date.parse() {
         Ok(entry_date) => entry_date,
         Err(_) => return Ok(HttpResponse::BadRequest().finish()),
     };
-    let user_id = match session.get::<i32>("user_id") {
-        Ok(user_id) => match user_id {
-            Some(user_id) => user_id,
-            None => return Ok(HttpResponse::InternalServerError().finish()),
-        },
-        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
-    };
+    let user_id = user.id();
 
     match DiaryEntrySkills::find_diary_entry_skills_by_date(&config, diary_entry_date, &user_id)
         .await
@@ -155,33 +395,72 @@ pub async fn show_skills(
     }
 }
 
-//Retrieves diary entries between two dates, or all if no dates
+// Retrieves a page of the user's diary entries matching the filters, in date order
 pub async fn index(
-    query: web::Query<DateRangeRequest>,
+    query: web::Query<DiaryEntryQuery>,
     config: web::Data<AppData>,
-    session: Session,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
-    let date_range: DateRangeRequest = query.into_inner();
-    let user_id = match session.get::<i32>("user_id") {
-        Ok(user_id) => match user_id {
-            Some(user_id) => user_id,
-            None => return Ok(HttpResponse::InternalServerError().finish()),
-        },
+    let query: DiaryEntryQuery = query.into_inner();
+    let user_id = user.id();
+    let include_skills = match includes_skills(query.include.as_deref()) {
+        Ok(include_skills) => include_skills,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e })),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+        }));
+    }
+
+    // One extra entry tells whether there is another page
+    let mut diary_entries = match DiaryEntry::find_page(&config, &query, limit + 1, &user_id).await
+    {
+        Ok(entries) => entries,
         Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
+    let next_cursor = if diary_entries.len() as i64 > limit {
+        diary_entries.truncate(limit as usize);
+        diary_entries
+            .last()
+            .map(|entry| entry.entry_date.to_string())
+    } else {
+        None
+    };
+    if !include_skills {
+        return Ok(HttpResponse::Ok().json(Page {
+            items: diary_entries,
+            next_cursor,
+        }));
+    }
 
-    let diary_entries =
-        match DiaryEntry::find_by_date_range_user(&config, date_range, &user_id).await {
-            Ok(entries) => entries,
-            Err(_) => return Ok(HttpResponse::BadRequest().finish()),
-        };
-    let mut updated_diary_entries: Vec<DiaryEntry> = Vec::new();
-    for diary_entry in diary_entries {
-        let created: i64 = sqlx::types::chrono::DateTime::timestamp(&diary_entry.created_at);
-        let updated: i64 = sqlx::types::chrono::DateTime::timestamp(&diary_entry.updated_at);
-        if created != updated {
-            updated_diary_entries.push(diary_entry);
-        }
+    // All the page's skills come back in one query, then are handed out by entry id
+    let entry_ids: Vec<i32> = diary_entries.iter().map(|entry| entry.id).collect();
+    let rated_skills = match RatedSkill::find_by_diary_entries(&config, &entry_ids, &user_id).await
+    {
+        Ok(rated_skills) => rated_skills,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let mut skills_by_entry: HashMap<i32, Vec<RatedSkill>> = HashMap::new();
+    for skill in rated_skills {
+        skills_by_entry
+            .entry(skill.diary_entry_id)
+            .or_default()
+            .push(skill);
     }
-    return Ok(HttpResponse::Ok().json(updated_diary_entries));
+    let items: Vec<DiaryEntryWithSkills> = diary_entries
+        .into_iter()
+        .map(|entry| {
+            let skills = skills_by_entry.remove(&entry.id).unwrap_or_default();
+            DiaryEntryWithSkills { entry, skills }
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(Page { items, next_cursor }))
+}
+
+pub fn entry_exists() -> HttpResponse {
+    HttpResponse::Conflict().json(ErrorResponse {
+        error: "There is already a diary entry for that date".to_string(),
+    })
 }
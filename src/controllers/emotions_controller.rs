@@ -0,0 +1,13 @@
+use crate::configuration::AppData;
+use crate::models::Emotion;
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+// Retrieves the emotions a user can rate on their diary card
+pub async fn index(config: web::Data<AppData>) -> actix_web::Result<HttpResponse> {
+    match Emotion::find_all(&config).await {
+        Ok(emotions) => Ok(HttpResponse::Ok().json(emotions)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
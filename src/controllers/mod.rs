@@ -1,17 +1,233 @@
+use crate::configuration::RatingScale;
+use crate::models::{UserSettings, WEEKDAYS};
+use crate::password_policy::PasswordPolicy;
 use chrono::{DateTime, Utc};
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::validate_email;
 
+pub mod account_controller;
+pub mod admin_skills_controller;
+pub mod authentication;
 pub mod credentials_controller;
+pub mod diary_cards_controller;
 pub mod diary_entries_controller;
+pub mod emotions_controller;
 pub mod health_check_controller;
+pub mod skill_categories_controller;
 pub mod skills_controller;
+pub mod stats_controller;
+pub mod urges_controller;
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct DiaryForm {
     pub entry_date: DateTime<Utc>,
-    pub skill_ids: Vec<i32>,
+    pub skills: Vec<SkillRating>,
     pub notes: String,
+    #[serde(default)]
+    pub emotions: Vec<EmotionRating>,
+    #[serde(default)]
+    pub urges: Vec<UrgeRating>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SkillRating {
+    pub skill_id: i32,
+    pub rating: i32,
+    // Optional ids of the steps used, for acronym skills like DEAR MAN
+    #[serde(default)]
+    pub steps: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct EmotionRating {
+    pub emotion_id: i32,
+    pub intensity: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct UrgeRating {
+    pub urge_id: i32,
+    pub intensity: i32,
+}
+
+impl DiaryForm {
+    pub fn skill_ids(&self) -> Vec<i32> {
+        self.skills.iter().map(|skill| skill.skill_id).collect()
+    }
+
+    pub fn rating_for(&self, skill_id: i32) -> Option<i32> {
+        self.skills
+            .iter()
+            .find(|skill| skill.skill_id == skill_id)
+            .map(|skill| skill.rating)
+    }
+
+    pub fn steps_for(&self, skill_id: i32) -> &[i32] {
+        self.skills
+            .iter()
+            .find(|skill| skill.skill_id == skill_id)
+            .map(|skill| skill.steps.as_slice())
+            .unwrap_or_default()
+    }
+
+    // Returns a message describing the first rating that falls outside the scale
+    pub fn validate_ratings(&self, scale: &RatingScale) -> Result<(), String> {
+        match self
+            .skills
+            .iter()
+            .find(|skill| !scale.contains(skill.rating))
+        {
+            Some(skill) => Err(format!(
+                "Rating {} for skill {} is outside the {} to {} scale",
+                skill.rating, skill.skill_id, scale.min, scale.max
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn emotion_ids(&self) -> Vec<i32> {
+        self.emotions
+            .iter()
+            .map(|emotion| emotion.emotion_id)
+            .collect()
+    }
+
+    pub fn intensity_for_emotion(&self, emotion_id: i32) -> Option<i32> {
+        self.emotions
+            .iter()
+            .find(|emotion| emotion.emotion_id == emotion_id)
+            .map(|emotion| emotion.intensity)
+    }
+
+    pub fn urge_ids(&self) -> Vec<i32> {
+        self.urges.iter().map(|urge| urge.urge_id).collect()
+    }
+
+    pub fn intensity_for_urge(&self, urge_id: i32) -> Option<i32> {
+        self.urges
+            .iter()
+            .find(|urge| urge.urge_id == urge_id)
+            .map(|urge| urge.intensity)
+    }
+
+    // Returns a message describing the first emotion or urge intensity that falls outside the scale
+    pub fn validate_intensities(&self, scale: &RatingScale) -> Result<(), String> {
+        if let Some(emotion) = self
+            .emotions
+            .iter()
+            .find(|emotion| !scale.contains(emotion.intensity))
+        {
+            return Err(format!(
+                "Intensity {} for emotion {} is outside the {} to {} scale",
+                emotion.intensity, emotion.emotion_id, scale.min, scale.max
+            ));
+        }
+        match self
+            .urges
+            .iter()
+            .find(|urge| !scale.contains(urge.intensity))
+        {
+            Some(urge) => Err(format!(
+                "Intensity {} for urge {} is outside the {} to {} scale",
+                urge.intensity, urge.urge_id, scale.min, scale.max
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SkillForm {
+    pub name: String,
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl SkillForm {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.category.trim().is_empty() {
+            return Err("A skill needs a name and a category".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SkillQuery {
+    pub category: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct IncludeQuery {
+    pub include: Option<String>,
+}
+
+// True when a comma separated ?include= asks for skills. Only skills can be included.
+pub fn includes_skills(include: Option<&str>) -> Result<bool, String> {
+    let mut skills = false;
+    for relation in include.unwrap_or_default().split(',') {
+        match relation.trim() {
+            "" => (),
+            "skills" => skills = true,
+            other => return Err(format!("{} cannot be included", other)),
+        }
+    }
+    Ok(skills)
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SkillCategoryForm {
+    pub category: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SkillOrderForm {
+    pub skill_ids: Vec<i32>,
+}
+
+/// One page of a listing. `next_cursor` is passed back as `cursor` for the following page
+/// and is null on the last one.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// Settings left out of the form keep their current value
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct SettingsForm {
+    pub week_start: Option<String>,
+    pub timezone: Option<String>,
+}
+
+impl SettingsForm {
+    pub fn apply_to(self, mut settings: UserSettings) -> Result<UserSettings, String> {
+        if let Some(week_start) = self.week_start {
+            let week_start = week_start.to_lowercase();
+            if !WEEKDAYS.contains(&week_start.as_str()) {
+                return Err(format!("{} is not a day of the week", week_start));
+            }
+            settings.week_start = week_start;
+        }
+        if let Some(timezone) = self.timezone {
+            settings.timezone = timezone;
+        }
+        Ok(settings)
+    }
+}
+
+// JSON body for error responses the frontend needs to tell apart
+#[derive(Serialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -26,3 +242,57 @@ pub struct SignupForm {
     pub name: String,
     pub password: Secret<String>,
 }
+
+pub const MAX_NAME_LENGTH: usize = 100;
+
+// What is wrong with each field of a form, keyed by the field's name
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
+impl SignupForm {
+    // Every problem with the form at once, so they can all be shown next to their fields
+    pub fn field_errors(&self, password_policy: &PasswordPolicy) -> FieldErrors {
+        let mut errors = FieldErrors::new();
+        let mut add = |field: &str, message: String| {
+            errors.entry(field.to_string()).or_default().push(message)
+        };
+        if !validate_email(self.email.trim()) {
+            add("email", "is not a valid email address".to_string());
+        }
+        let name_length = self.name.trim().chars().count();
+        if name_length == 0 {
+            add("name", "can't be blank".to_string());
+        } else if name_length > MAX_NAME_LENGTH {
+            add(
+                "name",
+                format!("can't be longer than {} characters", MAX_NAME_LENGTH),
+            );
+        }
+        let user_inputs = [self.email.as_str(), self.name.as_str()];
+        for message in password_policy.check(self.password.expose_secret(), &user_inputs) {
+            add("password", message);
+        }
+        errors
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PasswordChangeForm {
+    pub current_password: Secret<String>,
+    pub new_password: Secret<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForgotPasswordForm {
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PasswordResetForm {
+    pub token: Secret<String>,
+    pub password: Secret<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
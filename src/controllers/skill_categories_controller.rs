@@ -0,0 +1,13 @@
+use crate::configuration::AppData;
+use crate::models::SkillCategory;
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+// Retrieves the skill categories in the order the diary card shows them
+pub async fn index(config: web::Data<AppData>) -> actix_web::Result<HttpResponse> {
+    match SkillCategory::find_all(&config).await {
+        Ok(categories) => Ok(HttpResponse::Ok().json(categories)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
@@ -1,25 +1,195 @@
+use crate::catalogue::{self, CatalogueFormat};
 use crate::configuration::AppData;
-use crate::models::{Record, Skill};
+use crate::controllers::authentication::VerifiedUser;
+use crate::controllers::{ErrorResponse, ExportQuery, SkillForm, SkillOrderForm, SkillQuery};
+use crate::models::{
+    is_foreign_key_violation, Record, Skill, SkillPreference, SkillTree, UnitOfWork,
+};
 
 use actix_web::web;
 use actix_web::HttpResponse;
+use std::collections::HashSet;
 
+// Retrieves a global skill or one of the user's private skills, with its steps
 pub async fn show(
     params: web::Path<(String,)>,
     config: web::Data<AppData>,
+    user: VerifiedUser,
 ) -> actix_web::Result<HttpResponse> {
     let id = &params.0;
-    let skill_id: i32 = id.parse().unwrap();
+    let skill_id: i32 = match id.parse() {
+        Ok(skill_id) => skill_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
 
-    match Skill::find_by_id(&config, skill_id).await {
-        Ok(entry) => Ok(HttpResponse::Ok().json(entry)),
-        Err(_) => Ok(HttpResponse::NotFound().finish()),
+    let skill = match Skill::find_visible_by_id(&config, skill_id, &user.id()).await {
+        Ok(skill) => skill,
+        Err(_) => return Ok(HttpResponse::NotFound().finish()),
+    };
+    match Skill::find_steps(&config, skill.id).await {
+        Ok(steps) => Ok(HttpResponse::Ok().json(SkillTree { skill, steps })),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
     }
 }
 
-pub async fn index(config: web::Data<AppData>) -> actix_web::Result<HttpResponse> {
-    match Skill::find_all(&config).await {
+// Retrieves the user's effective skill list in their card order, optionally for one category
+pub async fn index(
+    query: web::Query<SkillQuery>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let category = query.into_inner().category;
+    match Skill::find_for_user(&config, &user.id(), category.as_deref()).await {
         Ok(entries) => Ok(HttpResponse::Ok().json(entries)),
         Err(_) => Ok(HttpResponse::NotFound().finish()),
     }
 }
+
+// Creates a skill private to the user
+pub async fn create(
+    form: web::Json<SkillForm>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let skill_form = form.into_inner();
+    if let Err(e) = skill_form.validate() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+    }
+    let skill = Skill {
+        id: 0,
+        name: skill_form.name,
+        category: skill_form.category,
+        description: skill_form.description,
+        user_id: Some(user.id()),
+        parent_id: None,
+    };
+
+    match skill.save(&config).await {
+        Ok(skill) => Ok(HttpResponse::Created().json(skill)),
+        Err(e) if is_foreign_key_violation(&e) => Ok(unknown_category()),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+// Hides a skill from the user's card
+pub async fn hide(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    set_hidden(&params.0, true, &config, user).await
+}
+
+// Shows a previously hidden skill on the user's card again
+pub async fn unhide(
+    params: web::Path<(String,)>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    set_hidden(&params.0, false, &config, user).await
+}
+
+async fn set_hidden(
+    id: &str,
+    hidden: bool,
+    config: &AppData,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let skill_id: i32 = match id.parse() {
+        Ok(skill_id) => skill_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    match Skill::find_visible_by_id(config, skill_id, &user_id).await {
+        Ok(_) => (),
+        Err(sqlx::Error::RowNotFound) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let mut unit_of_work = match UnitOfWork::begin(config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if SkillPreference::set_hidden(&mut unit_of_work, skill_id, hidden, &user_id)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Orders the user's card by the given skill ids
+pub async fn reorder(
+    form: web::Json<SkillOrderForm>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = user.id();
+    let skill_ids = form.into_inner().skill_ids;
+    let unique_ids: HashSet<i32> = skill_ids.iter().copied().collect();
+    if unique_ids.len() != skill_ids.len() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Each skill can only appear once in the order".to_string(),
+        }));
+    }
+    let mut unit_of_work = match UnitOfWork::begin(&config).await {
+        Ok(unit_of_work) => unit_of_work,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    let visible_skills = match Skill::find_by_ids(&mut unit_of_work, &skill_ids, &user_id).await {
+        Ok(skills) => skills,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    if visible_skills.len() != skill_ids.len() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "The order contains skills that do not exist".to_string(),
+        }));
+    }
+    if SkillPreference::reorder(&mut unit_of_work, &skill_ids, &user_id)
+        .await
+        .is_err()
+        || unit_of_work.commit().await.is_err()
+    {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Retrieves the global skill catalogue as json, yaml or csv
+pub async fn export(
+    query: web::Query<ExportQuery>,
+    config: web::Data<AppData>,
+    _user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    let format: CatalogueFormat = match query.into_inner().format {
+        Some(format) => match format.parse() {
+            Ok(format) => format,
+            Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e })),
+        },
+        None => CatalogueFormat::Json,
+    };
+    let rows = match catalogue::export(&config.pg_pool).await {
+        Ok(rows) => rows,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+    match catalogue::render(&rows, format) {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"skill_catalogue.{}\"",
+                    format.extension()
+                ),
+            ))
+            .body(body)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+pub fn unknown_category() -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse {
+        error: "Unknown skill category".to_string(),
+    })
+}
@@ -0,0 +1,29 @@
+use crate::configuration::AppData;
+use crate::controllers::authentication::VerifiedUser;
+use crate::models::{DateRangeRequest, Stats, Streaks};
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+// Retrieves the user's skill usage between two dates, either of which may be left open
+pub async fn index(
+    query: web::Query<DateRangeRequest>,
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    match Stats::for_user(&config, query.into_inner(), &user.id()).await {
+        Ok(stats) => Ok(HttpResponse::Ok().json(stats)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+// Retrieves the user's current and longest logging streaks and recent completion rates
+pub async fn streaks(
+    config: web::Data<AppData>,
+    user: VerifiedUser,
+) -> actix_web::Result<HttpResponse> {
+    match Streaks::for_user(&config, &user.id()).await {
+        Ok(streaks) => Ok(HttpResponse::Ok().json(streaks)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
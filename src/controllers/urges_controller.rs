@@ -0,0 +1,13 @@
+use crate::configuration::AppData;
+use crate::models::Urge;
+
+use actix_web::web;
+use actix_web::HttpResponse;
+
+// Retrieves the urges a user can rate on their diary card
+pub async fn index(config: web::Data<AppData>) -> actix_web::Result<HttpResponse> {
+    match Urge::find_all(&config).await {
+        Ok(urges) => Ok(HttpResponse::Ok().json(urges)),
+        Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+    }
+}
@@ -1,9 +1,16 @@
+pub mod catalogue;
 pub mod configuration;
 pub mod controllers;
+pub mod login_throttle;
+pub mod mailer;
 pub mod models;
+pub mod password_policy;
+pub mod seeds;
 
 use controllers::{
-    credentials_controller, diary_entries_controller, health_check_controller, skills_controller,
+    account_controller, admin_skills_controller, credentials_controller, diary_cards_controller,
+    diary_entries_controller, emotions_controller, health_check_controller,
+    skill_categories_controller, skills_controller, stats_controller, urges_controller,
 };
 
 use actix_cors::Cors;
@@ -51,6 +58,10 @@ pub async fn run(
                 "/diary_entries/{date}",
                 web::get().to(diary_entries_controller::show),
             )
+            .route(
+                "/diary_entries/{date}",
+                web::put().to(diary_entries_controller::upsert),
+            )
             .route(
                 "/diary_entries",
                 web::get().to(diary_entries_controller::index),
@@ -63,14 +74,90 @@ pub async fn run(
                 "/diary_entries/{id}",
                 web::patch().to(diary_entries_controller::update),
             )
+            .route(
+                "/diary_entries/{id}",
+                web::delete().to(diary_entries_controller::delete),
+            )
+            .route(
+                "/diary_entries/{id}/restore",
+                web::post().to(diary_entries_controller::restore),
+            )
+            .route(
+                "/diary_cards/{iso_week}",
+                web::get().to(diary_cards_controller::show),
+            )
             .route("/skills", web::get().to(skills_controller::index))
+            .route("/skills", web::post().to(skills_controller::create))
+            .route("/skills/order", web::put().to(skills_controller::reorder))
+            .route("/skills/export", web::get().to(skills_controller::export))
             .route("/skills/{id}", web::get().to(skills_controller::show))
+            .route(
+                "/skills/{id}/hidden",
+                web::put().to(skills_controller::hide),
+            )
+            .route(
+                "/skills/{id}/hidden",
+                web::delete().to(skills_controller::unhide),
+            )
+            .route(
+                "/admin/skills",
+                web::post().to(admin_skills_controller::create),
+            )
+            .route(
+                "/admin/skills/{id}",
+                web::patch().to(admin_skills_controller::update),
+            )
+            .route(
+                "/admin/skills/{id}",
+                web::delete().to(admin_skills_controller::delete),
+            )
+            .route(
+                "/admin/skills/{id}/category",
+                web::put().to(admin_skills_controller::recategorize),
+            )
+            .route(
+                "/skill_categories",
+                web::get().to(skill_categories_controller::index),
+            )
+            .route("/stats", web::get().to(stats_controller::index))
+            .route("/streaks", web::get().to(stats_controller::streaks))
+            .route("/emotions", web::get().to(emotions_controller::index))
+            .route("/urges", web::get().to(urges_controller::index))
+            .route(
+                "/account/settings",
+                web::get().to(account_controller::show_settings),
+            )
+            .route(
+                "/account/settings",
+                web::patch().to(account_controller::update_settings),
+            )
+            .route(
+                "/account/password",
+                web::post().to(account_controller::update_password),
+            )
+            .route(
+                "/account/verification",
+                web::post().to(credentials_controller::resend_verification),
+            )
             .route("/login", web::post().to(credentials_controller::login))
             .route("/signup", web::post().to(credentials_controller::signup))
             .route(
                 "/session_name",
                 web::get().to(credentials_controller::session_name),
             )
+            .route(
+                "/password/forgot",
+                web::post().to(credentials_controller::forgot_password),
+            )
+            .route(
+                "/password/reset",
+                web::post().to(credentials_controller::reset_password),
+            )
+            .route(
+                "/verify_email",
+                web::get().to(credentials_controller::verify_email),
+            )
+            .route("/whoami", web::get().to(credentials_controller::whoami))
             .route("/logout", web::get().to(credentials_controller::logout))
             .app_data(app_data.clone())
             .app_data(Data::new(HmacSecret(hmac_secret.clone())))
@@ -0,0 +1,274 @@
+use crate::configuration::LoginThrottleSettings;
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Past this many keys the in-memory store sweeps out the expired ones
+const MAX_IN_MEMORY_KEYS: usize = 10_000;
+
+/// Counts failed logins per email and per IP address and locks either out
+/// once it has failed too often. Every failure past the allowance locks the
+/// email or address out for twice as long as the one before, up to a maximum.
+///
+/// Counts live in Redis so every worker sees the same numbers. When Redis
+/// can't be reached they are kept in this process instead.
+pub struct LoginThrottle {
+    settings: LoginThrottleSettings,
+    // Keeps apps sharing a Redis instance, such as test runs, apart
+    namespace: String,
+    redis: Option<ConnectionManager>,
+    memory: InMemoryCounters,
+}
+
+// Whether a login attempt is counted against an email or the address it came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Subject {
+    Email,
+    Ip,
+}
+
+impl Subject {
+    fn name(&self) -> &'static str {
+        match self {
+            Subject::Email => "email",
+            Subject::Ip => "ip",
+        }
+    }
+}
+
+impl LoginThrottle {
+    pub async fn connect(
+        settings: LoginThrottleSettings,
+        redis_uri: &str,
+        namespace: &str,
+    ) -> Self {
+        let redis = match redis::Client::open(redis_uri) {
+            Ok(client) => ConnectionManager::new(client).await,
+            Err(e) => Err(e),
+        };
+        let redis = match redis {
+            Ok(redis) => Some(redis),
+            Err(e) => {
+                tracing::warn!(
+                    "Unable to connect to Redis, counting login attempts in memory: {:?}",
+                    e
+                );
+                None
+            }
+        };
+        Self {
+            settings,
+            namespace: namespace.to_string(),
+            redis,
+            memory: InMemoryCounters::default(),
+        }
+    }
+
+    pub fn in_memory(settings: LoginThrottleSettings, namespace: &str) -> Self {
+        Self {
+            settings,
+            namespace: namespace.to_string(),
+            redis: None,
+            memory: InMemoryCounters::default(),
+        }
+    }
+
+    fn key(&self, kind: &str, subject: Subject, value: &str) -> String {
+        format!(
+            "login_throttle:{}:{}:{}:{}",
+            self.namespace,
+            kind,
+            subject.name(),
+            value.trim().to_lowercase()
+        )
+    }
+
+    fn max_failures(&self, subject: Subject) -> u64 {
+        match subject {
+            Subject::Email => self.settings.max_failures_per_email,
+            Subject::Ip => self.settings.max_failures_per_ip,
+        }
+    }
+
+    // How long a lockout after this many failures lasts, if there is one
+    fn lockout_seconds(&self, subject: Subject, failures: u64) -> Option<u64> {
+        let over = failures
+            .checked_sub(self.max_failures(subject))?
+            .checked_sub(1)?;
+        let factor = 1u64.checked_shl(over as u32).unwrap_or(u64::MAX);
+        Some(
+            self.settings
+                .base_lockout_seconds
+                .saturating_mul(factor)
+                .min(self.settings.max_lockout_seconds),
+        )
+    }
+
+    /// Seconds until the email and address may try again, when either is locked out.
+    pub async fn retry_after(&self, email: &str, ip: &str) -> Option<u64> {
+        let email_lock = self
+            .lock_ttl(&self.key("lock", Subject::Email, email))
+            .await;
+        let ip_lock = self.lock_ttl(&self.key("lock", Subject::Ip, ip)).await;
+        email_lock.max(ip_lock)
+    }
+
+    /// Counts a failed login, locking out the email or address once it has
+    /// failed too often. Returns how long the attempt after this one must wait.
+    pub async fn record_failure(&self, email: &str, ip: &str) -> Option<u64> {
+        let mut retry_after = None;
+        for (subject, value) in [(Subject::Email, email), (Subject::Ip, ip)] {
+            let failures = self
+                .increment(
+                    &self.key("failures", subject, value),
+                    self.settings.window_seconds,
+                )
+                .await;
+            if let Some(seconds) = self.lockout_seconds(subject, failures) {
+                self.lock(&self.key("lock", subject, value), seconds).await;
+                tracing::warn!(
+                    target: "audit",
+                    subject = subject.name(),
+                    email = %email,
+                    ip = %ip,
+                    failures,
+                    lockout_seconds = seconds,
+                    "Locked out login after repeated failures"
+                );
+                retry_after = retry_after.max(Some(seconds));
+            }
+        }
+        retry_after
+    }
+
+    /// Forgets an email's failures once it logs in. The address keeps its
+    /// count, so one good account can't be used to reset it.
+    pub async fn record_success(&self, email: &str) {
+        self.delete(&self.key("failures", Subject::Email, email))
+            .await;
+    }
+}
+
+// Each operation uses Redis when it is there and answers, and memory otherwise
+impl LoginThrottle {
+    async fn increment(&self, key: &str, window_seconds: u64) -> u64 {
+        if let Some(redis) = &self.redis {
+            let mut connection = redis.clone();
+            let result: Result<u64, redis::RedisError> = async {
+                let count: u64 = redis::cmd("INCR")
+                    .arg(key)
+                    .query_async(&mut connection)
+                    .await?;
+                // The window starts at the first failure
+                if count == 1 {
+                    redis::cmd("EXPIRE")
+                        .arg(key)
+                        .arg(window_seconds)
+                        .query_async::<_, ()>(&mut connection)
+                        .await?;
+                }
+                Ok(count)
+            }
+            .await;
+            match result {
+                Ok(count) => return count,
+                Err(e) => {
+                    tracing::warn!("Redis failed, counting login attempts in memory: {:?}", e)
+                }
+            }
+        }
+        self.memory
+            .increment(key, Duration::from_secs(window_seconds))
+    }
+
+    async fn lock(&self, key: &str, seconds: u64) {
+        if let Some(redis) = &self.redis {
+            let mut connection = redis.clone();
+            let result = redis::cmd("SET")
+                .arg(key)
+                .arg(1)
+                .arg("EX")
+                .arg(seconds)
+                .query_async::<_, ()>(&mut connection)
+                .await;
+            match result {
+                Ok(()) => return,
+                Err(e) => tracing::warn!("Redis failed, locking out login in memory: {:?}", e),
+            }
+        }
+        self.memory.set(key, Duration::from_secs(seconds))
+    }
+
+    // Lockouts made in memory while Redis was down still count once it is back
+    async fn lock_ttl(&self, key: &str) -> Option<u64> {
+        let in_memory = self.memory.ttl(key);
+        if let Some(redis) = &self.redis {
+            let mut connection = redis.clone();
+            let result: Result<i64, redis::RedisError> = redis::cmd("TTL")
+                .arg(key)
+                .query_async(&mut connection)
+                .await;
+            match result {
+                // TTL is -2 for a missing key and -1 for one without an expiry
+                Ok(ttl) => return (ttl > 0).then_some(ttl as u64).max(in_memory),
+                Err(e) => tracing::warn!("Redis failed, checking lockouts in memory: {:?}", e),
+            }
+        }
+        in_memory
+    }
+
+    async fn delete(&self, key: &str) {
+        if let Some(redis) = &self.redis {
+            let mut connection = redis.clone();
+            let result = redis::cmd("DEL")
+                .arg(key)
+                .query_async::<_, ()>(&mut connection)
+                .await;
+            if let Err(e) = result {
+                tracing::warn!("Redis failed, clearing login attempts in memory: {:?}", e);
+            }
+        }
+        // Also clear anything counted in memory while Redis was down
+        self.memory.delete(key)
+    }
+}
+
+// Counters that expire, for when Redis isn't available
+#[derive(Default)]
+struct InMemoryCounters {
+    entries: Mutex<HashMap<String, (u64, Instant)>>,
+}
+
+impl InMemoryCounters {
+    fn increment(&self, key: &str, window: Duration) -> u64 {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() > MAX_IN_MEMORY_KEYS {
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+        let entry = entries.entry(key.to_string()).or_insert((0, now + window));
+        if entry.1 <= now {
+            *entry = (0, now + window);
+        }
+        entry.0 += 1;
+        entry.0
+    }
+
+    fn set(&self, key: &str, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(key.to_string(), (1, Instant::now() + ttl));
+    }
+
+    // Whole seconds left, rounded up, on a key that hasn't expired
+    fn ttl(&self, key: &str) -> Option<u64> {
+        let entries = self.entries.lock().unwrap();
+        let (_, expires_at) = entries.get(key)?;
+        let left = expires_at.checked_duration_since(Instant::now())?;
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    fn delete(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
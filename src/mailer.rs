@@ -0,0 +1,90 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers the emails the app sends, such as password reset links.
+/// AppData holds one behind an Arc so tests can swap in their own.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), anyhow::Error>;
+}
+
+// Writes each email to the log instead of sending it, for local development
+#[derive(Debug, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    #[tracing::instrument(name = "Logging email", skip(self, email), fields(to = %email.to))]
+    async fn send(&self, email: Email) -> Result<(), anyhow::Error> {
+        tracing::info!("{}\n\n{}", email.subject, email.body);
+        Ok(())
+    }
+}
+
+// Writes each email to its own file in a directory, for reading links in dev
+#[derive(Debug)]
+pub struct FileMailer {
+    directory: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    #[tracing::instrument(name = "Writing email to file", skip(self, email), fields(to = %email.to))]
+    async fn send(&self, email: Email) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(&self.directory).with_context(|| {
+            format!(
+                "Unable to create mail directory {}",
+                self.directory.display()
+            )
+        })?;
+        let path = self.directory.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+            uuid::Uuid::new_v4().to_simple()
+        ));
+        let contents = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            email.to, email.subject, email.body
+        );
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Unable to write email to {}", path.display()))
+    }
+}
+
+// Keeps every email it is given, so tests can read what would have been sent
+#[derive(Debug, Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl InMemoryMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, email: Email) -> Result<(), anyhow::Error> {
+        self.sent.lock().unwrap().push(email);
+        Ok(())
+    }
+}
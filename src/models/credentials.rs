@@ -1,5 +1,6 @@
-use crate::configuration::{AppData, Environment};
+use crate::configuration::AppData;
 use crate::controllers::{LoginForm, SignupForm};
+use crate::models::UnitOfWork;
 use actix_web::rt::task::JoinHandle;
 use anyhow::Context;
 use argon2::password_hash::SaltString;
@@ -22,33 +23,32 @@ pub struct Credentials {
 }
 
 #[tracing::instrument(name = "Create new user", skip(user, config))]
-pub async fn create_user(user: SignupForm, config: &AppData) -> Result<(), anyhow::Error> {
+pub async fn create_user(user: SignupForm, config: &AppData) -> Result<i32, anyhow::Error> {
     let mut transaction = config.pg_pool.begin().await?;
-    let email = user.email;
-    let name = user.name;
+    let email = user.email.trim().to_string();
+    let name = user.name.trim().to_string();
     let password = user.password;
     let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
         .await?
         .context("Failed to hash password")?;
     let query_statement = r#"
         INSERT INTO users (email, name, password_hash)
-        VALUES ($1, $2, $3)"#;
-    sqlx::query(query_statement)
+        VALUES ($1, $2, $3)
+        RETURNING id"#;
+    let (user_id,): (i32,) = sqlx::query_as(query_statement)
         .bind(email)
         .bind(name)
         .bind(password_hash.expose_secret())
-        .execute(&mut transaction)
+        .fetch_one(&mut transaction)
         .await
         .map_err(|e| {
             tracing::error!("failed to execute query: {:?}", e);
             e
         })?;
 
-    if let Environment::Dev = config.env {
-        transaction.commit().await?;
-    }
+    transaction.commit().await?;
 
-    Ok(())
+    Ok(user_id)
 }
 
 #[tracing::instrument(name = "Get stored credentials", skip(email, config))]
@@ -60,7 +60,7 @@ async fn get_stored_credentials(
         r#"
         SELECT id, password_hash
         FROM users
-        WHERE email = $1
+        WHERE lower(email) = lower($1)
         "#,
         email,
     )
@@ -87,6 +87,73 @@ pub async fn get_name(user_id: i32, config: &AppData) -> Result<String, anyhow::
     Ok(row.name)
 }
 
+#[tracing::instrument(name = "Get email", skip(config))]
+pub async fn get_email(user_id: i32, config: &AppData) -> Result<String, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT email
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id,
+    )
+    .fetch_one(&config.pg_pool)
+    .await
+    .context("Failed to performed a query to retrieve email")?;
+    Ok(row.email)
+}
+
+#[tracing::instrument(name = "Find user by email", skip(email, config))]
+pub async fn find_user_id_by_email(
+    email: &str,
+    config: &AppData,
+) -> Result<Option<i32>, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id
+        FROM users
+        WHERE lower(email) = lower($1)
+        "#,
+        email,
+    )
+    .fetch_optional(&config.pg_pool)
+    .await
+    .context("Failed to performed a query to find the user by email")?;
+    Ok(row.map(|row| row.id))
+}
+
+#[tracing::instrument(name = "Check admin role", skip(config))]
+pub async fn is_admin(user_id: i32, config: &AppData) -> Result<bool, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT role = 'admin' AS "is_admin!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id,
+    )
+    .fetch_optional(&config.pg_pool)
+    .await
+    .context("Failed to performed a query to retrieve the user's role")?;
+    Ok(row.map(|row| row.is_admin).unwrap_or(false))
+}
+
+#[tracing::instrument(name = "Check email verified", skip(config))]
+pub async fn is_email_verified(user_id: i32, config: &AppData) -> Result<bool, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT email_verified_at IS NOT NULL AS "is_verified!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id,
+    )
+    .fetch_optional(&config.pg_pool)
+    .await
+    .context("Failed to performed a query to retrieve the user's verification")?;
+    Ok(row.map(|row| row.is_verified).unwrap_or(false))
+}
+
 #[tracing::instrument(name = "Validate credentials", skip(config, login_data))]
 pub async fn validate_credentials(
     config: &AppData,
@@ -139,21 +206,20 @@ fn verify_password_hash(
         .map_err(AuthError::InvalidCredentials)
 }
 
-#[tracing::instrument(name = "Change password", skip(password, config))]
+#[tracing::instrument(name = "Change password", skip(password, unit_of_work))]
 pub async fn change_password(
-    user_id: uuid::Uuid,
+    user_id: i32,
     password: Secret<String>,
-    config: &AppData,
+    unit_of_work: &mut UnitOfWork<'_>,
 ) -> Result<(), anyhow::Error> {
-    let mut transaction = config.pg_pool.begin().await?;
     let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
         .await?
         .context("Failed to hash password")?;
-    let query_statement = r#"UPDATE users SET password_hash = $1 WHERE user_id = $2"#;
+    let query_statement = r#"UPDATE users SET password_hash = $1 WHERE id = $2"#;
     sqlx::query(query_statement)
         .bind(password_hash.expose_secret())
         .bind(user_id)
-        .execute(&mut transaction)
+        .execute(unit_of_work.transaction())
         .await
         .context("Failed to change user's password in the database.")?;
     Ok(())
@@ -0,0 +1,129 @@
+use crate::configuration::AppData;
+use crate::models::{DateRangeRequest, DiaryEntry, RatedSkill, Skill};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// A week of diary entries as a grid: one row per skill, one cell per day.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DiaryCard {
+    pub week: String,
+    pub days: Vec<DiaryCardDay>,
+    pub rows: Vec<DiaryCardRow>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DiaryCardDay {
+    pub date: NaiveDate,
+    // None on days without an entry
+    pub diary_entry_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DiaryCardRow {
+    pub skill_id: i32,
+    pub name: String,
+    pub category: String,
+    // One cell per day, None where the skill wasn't logged
+    pub cells: Vec<Option<DiaryCardCell>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DiaryCardCell {
+    pub rating: Option<i32>,
+    pub steps: Vec<i32>,
+}
+
+/// The Monday of an ISO week written like `2022-W06`.
+pub fn parse_iso_week(iso_week: &str) -> Option<NaiveDate> {
+    let (year, week) = iso_week.split_once("-W")?;
+    if year.len() != 4 || week.len() != 2 {
+        return None;
+    }
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)
+}
+
+// The card starts on the user's week start day that keeps the most of the ISO week,
+// so a Sunday start begins the day before the ISO week and a Tuesday start the day after.
+pub fn card_start(iso_monday: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let offset = (week_start.num_days_from_monday() as i64 + 3) % 7 - 3;
+    iso_monday + Duration::days(offset)
+}
+
+impl DiaryCard {
+    #[tracing::instrument(name = "Building diary card for a week", skip(config))]
+    pub async fn for_week(
+        config: &AppData,
+        iso_monday: NaiveDate,
+        week_start: Weekday,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
+        let start = card_start(iso_monday, week_start);
+        let dates: Vec<NaiveDate> = (0..7).map(|day| start + Duration::days(day)).collect();
+        let date_range = DateRangeRequest {
+            start: Some(dates[0]),
+            end: Some(dates[6]),
+        };
+        let entries = DiaryEntry::find_by_date_range_user(config, date_range, user_id).await?;
+        let entry_ids: Vec<i32> = entries.iter().map(|entry| entry.id).collect();
+        let rated_skills = RatedSkill::find_by_diary_entries(config, &entry_ids, user_id).await?;
+
+        let days: Vec<DiaryCardDay> = dates
+            .iter()
+            .map(|date| DiaryCardDay {
+                date: *date,
+                diary_entry_id: entries
+                    .iter()
+                    .find(|entry| entry.entry_date == *date)
+                    .map(|entry| entry.id),
+            })
+            .collect();
+
+        // The user's card in their order, then anything logged that week that has
+        // since been hidden or retired
+        let mut rows: Vec<DiaryCardRow> = Skill::find_for_user(config, user_id, None)
+            .await?
+            .into_iter()
+            .map(|skill| DiaryCardRow {
+                skill_id: skill.id,
+                name: skill.name,
+                category: skill.category,
+                cells: (0..7).map(|_| None).collect(),
+            })
+            .collect();
+        for rated_skill in rated_skills {
+            let day = days
+                .iter()
+                .position(|day| day.diary_entry_id == Some(rated_skill.diary_entry_id));
+            let day = match day {
+                Some(day) => day,
+                None => continue,
+            };
+            let row = match rows
+                .iter()
+                .position(|row| row.skill_id == rated_skill.skill_id)
+            {
+                Some(row) => row,
+                None => {
+                    rows.push(DiaryCardRow {
+                        skill_id: rated_skill.skill_id,
+                        name: rated_skill.name.clone(),
+                        category: rated_skill.category.clone(),
+                        cells: (0..7).map(|_| None).collect(),
+                    });
+                    rows.len() - 1
+                }
+            };
+            rows[row].cells[day] = Some(DiaryCardCell {
+                rating: rated_skill.rating,
+                steps: rated_skill.steps,
+            });
+        }
+
+        let iso_week = iso_monday.iso_week();
+        Ok(DiaryCard {
+            week: format!("{}-W{:02}", iso_week.year(), iso_week.week()),
+            days,
+            rows,
+        })
+    }
+}
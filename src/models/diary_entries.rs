@@ -1,9 +1,8 @@
-use crate::configuration::{AppData, Environment};
-use crate::models::Record;
-use async_trait::async_trait;
+use crate::configuration::AppData;
+use crate::models::UnitOfWork;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, Row};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
 pub struct DiaryEntry {
@@ -15,24 +14,50 @@ pub struct DiaryEntry {
     pub notes: String,
 }
 
+/// How long a deleted diary entry can still be restored before it is purged.
+pub const RESTORE_WINDOW_DAYS: i32 = 30;
+
 #[derive(Deserialize, Debug)]
 pub struct DateRangeRequest {
     pub start: Option<sqlx::types::chrono::NaiveDate>,
     pub end: Option<sqlx::types::chrono::NaiveDate>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters for listing diary entries. Either end of the date range may be left open.
+/// The cursor is the entry date the previous page ended on.
+#[derive(Deserialize, Debug, Default)]
+pub struct DiaryEntryQuery {
+    pub start: Option<sqlx::types::chrono::NaiveDate>,
+    pub end: Option<sqlx::types::chrono::NaiveDate>,
+    pub has_notes: Option<bool>,
+    pub skill_id: Option<i32>,
+    pub category: Option<String>,
+    #[serde(default)]
+    pub sort: SortOrder,
+    pub cursor: Option<sqlx::types::chrono::NaiveDate>,
+    pub limit: Option<i64>,
+    pub include: Option<String>,
+}
+
 #[tracing::instrument(
     name = "Saving diary entry from form and user_id in the database",
-    skip(config)
+    skip(unit_of_work)
 )]
 pub async fn save_from_form(
     entry_date: &DateTime<Utc>,
     notes: &str,
-    config: &AppData,
+    unit_of_work: &mut UnitOfWork<'_>,
     user_id: &i32,
 ) -> Result<DiaryEntry, sqlx::Error> {
     let current_time = Utc::now();
-    let mut transaction = config.pg_pool.begin().await?;
     let query_statement = r#"
     INSERT INTO diary_entries (user_id, entry_date, created_at, updated_at, notes)
     VALUES ($1, $2, $3, $4, $5) RETURNING id, user_id, entry_date, created_at, updated_at, notes
@@ -43,35 +68,65 @@ pub async fn save_from_form(
         .bind(current_time)
         .bind(current_time)
         .bind(notes)
-        .fetch_one(&mut transaction)
+        .fetch_one(unit_of_work.transaction())
         .await
         .map_err(|e| {
             tracing::error!("failed to execute query: {:?}", e);
             e
         })?;
 
-    if let Environment::Dev = config.env {
-        transaction.commit().await?;
-    }
-
     Ok(query)
 }
 
+// Creates the user's entry for the day or replaces its notes, true when it was created
+#[tracing::instrument(
+    name = "Upserting diary entry by date and user_id in the database",
+    skip(unit_of_work)
+)]
+pub async fn upsert_from_form(
+    entry_date: &DateTime<Utc>,
+    notes: &str,
+    unit_of_work: &mut UnitOfWork<'_>,
+    user_id: &i32,
+) -> Result<(DiaryEntry, bool), sqlx::Error> {
+    let current_time = Utc::now();
+    let query_statement = r#"
+    INSERT INTO diary_entries (user_id, entry_date, created_at, updated_at, notes)
+    VALUES ($1, $2, $3, $4, $5)
+    ON CONFLICT (user_id, entry_date) WHERE deleted_at IS NULL
+    DO UPDATE SET updated_at = EXCLUDED.updated_at, notes = EXCLUDED.notes
+    RETURNING id, user_id, entry_date, created_at, updated_at, notes, xmax = 0 AS inserted
+    "#;
+    let row = sqlx::query(query_statement)
+        .bind(user_id)
+        .bind(entry_date)
+        .bind(current_time)
+        .bind(current_time)
+        .bind(notes)
+        .fetch_one(unit_of_work.transaction())
+        .await
+        .map_err(|e| {
+            tracing::error!("failed to execute query: {:?}", e);
+            e
+        })?;
+
+    Ok((DiaryEntry::from_row(&row)?, row.try_get("inserted")?))
+}
+
 #[tracing::instrument(
     name = "Updating diary entry by id and user_id in the database",
-    skip(config)
+    skip(unit_of_work)
 )]
 pub async fn update_diary_entry(
     id: &i32,
     notes: &str,
-    config: &AppData,
+    unit_of_work: &mut UnitOfWork<'_>,
     user_id: &i32,
 ) -> Result<DiaryEntry, sqlx::Error> {
-    let mut transaction = config.pg_pool.begin().await?;
     let query_statement = r#"
     UPDATE diary_entries
     SET updated_at = $1, notes = $2
-    WHERE id = $3 AND user_id = $4
+    WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL
     RETURNING id, user_id, entry_date, created_at, updated_at, notes
     "#;
     let query: DiaryEntry = sqlx::query_as(query_statement)
@@ -79,55 +134,27 @@ pub async fn update_diary_entry(
         .bind(notes)
         .bind(id)
         .bind(user_id)
-        .fetch_one(&mut transaction)
+        .fetch_one(unit_of_work.transaction())
         .await
         .map_err(|e| {
             tracing::error!("failed to execute query: {:?}", e);
             e
         })?;
 
-    if let Environment::Dev = config.env {
-        transaction.commit().await?;
-    }
-
     Ok(query)
 }
 
-#[async_trait]
-impl Record for DiaryEntry {
-    #[tracing::instrument(name = "Saving diary entry in the database", skip(config))]
-    async fn save(self, config: &AppData) -> Result<Self, sqlx::Error> {
-        let mut transaction = config.pg_pool.begin().await?;
-        let query_statement = r#"
-    INSERT INTO diary_entries (id, user_id, entry_date, created_at, updated_at, notes)
-    VALUES ($1, $2, $3, $4, $5, $6) RETURNING id, user_id, entry_date, created_at, updated_at, notes
-    "#;
-        let query: DiaryEntry = sqlx::query_as(query_statement)
-            .bind(self.id)
-            .bind(self.user_id)
-            .bind(self.entry_date)
-            .bind(self.created_at)
-            .bind(self.updated_at)
-            .bind(&self.notes)
-            .fetch_one(&mut transaction)
-            .await
-            .map_err(|e| {
-                tracing::error!("failed to execute query: {:?}", e);
-                e
-            })?;
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
-
-        Ok(query)
-    }
-
-    #[tracing::instrument(name = "Retrieving diary entry by id from the database", skip(config))]
-    async fn find_by_id(config: &AppData, id: i32) -> Result<Self, sqlx::Error> {
+impl DiaryEntry {
+    #[tracing::instrument(
+        name = "Retrieving diary entry by id and user_id from the database",
+        skip(config)
+    )]
+    pub async fn find_by_id(config: &AppData, id: i32, user_id: &i32) -> Result<Self, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
-        let query_statement = r#"SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE id = $1"#;
-        let diary_entry = sqlx::query_as(query_statement)
+        let query_statement = r#"SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#;
+        let diary_entry: DiaryEntry = sqlx::query_as(query_statement)
             .bind(id)
+            .bind(user_id)
             .fetch_one(&mut transaction)
             .await
             .map_err(|e| {
@@ -135,9 +162,7 @@ impl Record for DiaryEntry {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(diary_entry)
     }
@@ -154,7 +179,7 @@ impl DiaryEntry {
         user_id: &i32,
     ) -> Result<Self, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
-        let query_statement = r#"SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE entry_date = $1 AND user_id = $2"#;
+        let query_statement = r#"SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE entry_date = $1 AND user_id = $2 AND deleted_at IS NULL"#;
         let diary_entry: DiaryEntry = sqlx::query_as(query_statement)
             .bind(date)
             .bind(user_id)
@@ -165,86 +190,197 @@ impl DiaryEntry {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
         Ok(diary_entry)
     }
 }
 
 impl DiaryEntry {
-    #[tracing::instrument(name = "Retrieving diary entries from database", skip(config))]
-    pub async fn find_by_date_range(
+    #[tracing::instrument(
+        name = "Retrieving diary entries by date range and user from database",
+        skip(config)
+    )]
+    pub async fn find_by_date_range_user(
         config: &AppData,
         date_range: DateRangeRequest,
+        user_id: &i32,
     ) -> Result<Vec<Self>, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
 
-        let query_statement: String;
-        if date_range.start.is_none() || date_range.end.is_none() {
-            query_statement =
-                r#"SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries"#
-                    .to_string();
-        } else {
-            query_statement = format!(
-                "SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE entry_date BETWEEN '{}' AND '{}';",
-                date_range.start.unwrap(),
-                date_range.end.unwrap()
-            );
-        }
-        let diary_entries: Vec<DiaryEntry> = sqlx::query_as(&query_statement)
-            .fetch_all(&mut transaction)
+        let diary_entries: Vec<DiaryEntry> = sqlx::query_as!(
+            DiaryEntry,
+            r#"
+            SELECT id, user_id as "user_id!", entry_date, created_at,
+                updated_at as "updated_at!", notes as "notes!"
+            FROM diary_entries
+            WHERE user_id = $1 AND deleted_at IS NULL
+            AND ($2::date IS NULL OR entry_date >= $2)
+            AND ($3::date IS NULL OR entry_date <= $3)
+            ORDER BY entry_date
+            "#,
+            user_id,
+            date_range.start,
+            date_range.end,
+        )
+        .fetch_all(&mut transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+        transaction.commit().await?;
+
+        Ok(diary_entries)
+    }
+}
+
+impl DiaryEntry {
+    #[tracing::instrument(
+        name = "Soft deleting diary entry by id and user_id in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn soft_delete(
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: i32,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE diary_entries SET deleted_at = $3
+    WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+    RETURNING id, user_id, entry_date, created_at, updated_at, notes
+    "#;
+        let diary_entry: DiaryEntry = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(user_id)
+            .bind(Utc::now())
+            .fetch_one(unit_of_work.transaction())
             .await
             .map_err(|e| {
                 tracing::error!("Failed to execute query: {:?}", e);
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        Ok(diary_entry)
+    }
 
-        Ok(diary_entries)
+    #[tracing::instrument(
+        name = "Restoring deleted diary entry by id and user_id in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn restore(
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: i32,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE diary_entries SET deleted_at = NULL
+    WHERE id = $1 AND user_id = $2
+    AND deleted_at > now() - make_interval(days => $3)
+    RETURNING id, user_id, entry_date, created_at, updated_at, notes
+    "#;
+        let diary_entry: DiaryEntry = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(user_id)
+            .bind(RESTORE_WINDOW_DAYS)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(diary_entry)
+    }
+
+    // Entries deleted longer ago than the restore window are removed for good,
+    // along with their skill links. Steps, emotions and urges cascade.
+    #[tracing::instrument(
+        name = "Purging expired deleted diary entries by user_id in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn purge_expired(
+        unit_of_work: &mut UnitOfWork<'_>,
+        user_id: &i32,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    WITH expired AS (
+        DELETE FROM diary_entries
+        WHERE user_id = $1 AND deleted_at <= now() - make_interval(days => $2)
+        RETURNING id
+    )
+    DELETE FROM diary_entries_skills USING expired
+    WHERE diary_entries_skills.diary_entry_id = expired.id
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(RESTORE_WINDOW_DAYS)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
     }
 }
 
 impl DiaryEntry {
     #[tracing::instrument(
-        name = "Retrieving diary entries by date range and user from database",
+        name = "Retrieving a page of filtered diary entries by user from database",
         skip(config)
     )]
-    pub async fn find_by_date_range_user(
+    pub async fn find_page(
         config: &AppData,
-        date_range: DateRangeRequest,
+        query: &DiaryEntryQuery,
+        limit: i64,
         user_id: &i32,
     ) -> Result<Vec<Self>, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
 
-        let query_statement: String;
-        if date_range.start.is_none() || date_range.end.is_none() {
-            query_statement = format!(
-                "SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE user_id = {}",
-                user_id
-            );
-        } else {
-            query_statement = format!(
-                "SELECT id, user_id, entry_date, created_at, updated_at, notes FROM diary_entries WHERE entry_date BETWEEN '{}' AND '{}' AND user_id = {};",
-                date_range.start.unwrap(),
-                date_range.end.unwrap(),
-                user_id
-            );
-        }
-        let diary_entries: Vec<DiaryEntry> = sqlx::query_as(&query_statement)
-            .fetch_all(&mut transaction)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to execute query: {:?}", e);
-                e
-            })?;
+        // Each user has one entry per day, so the entry date alone orders the pages
+        let diary_entries: Vec<DiaryEntry> = sqlx::query_as!(
+            DiaryEntry,
+            r#"
+            SELECT id, user_id as "user_id!", entry_date, created_at,
+                updated_at as "updated_at!", notes as "notes!"
+            FROM diary_entries
+            WHERE user_id = $1 AND deleted_at IS NULL
+            AND ($2::date IS NULL OR entry_date >= $2)
+            AND ($3::date IS NULL OR entry_date <= $3)
+            AND ($4::bool IS NULL OR (COALESCE(notes, '') <> '') = $4)
+            AND ($5::int IS NULL OR EXISTS (
+                SELECT 1 FROM diary_entries_skills
+                WHERE diary_entries_skills.diary_entry_id = diary_entries.id
+                AND diary_entries_skills.skills_id = $5))
+            AND ($6::text IS NULL OR EXISTS (
+                SELECT 1 FROM diary_entries_skills
+                JOIN skills ON skills.id = diary_entries_skills.skills_id
+                WHERE diary_entries_skills.diary_entry_id = diary_entries.id
+                AND skills.category = $6))
+            AND ($7::date IS NULL OR (CASE WHEN $8 THEN entry_date < $7 ELSE entry_date > $7 END))
+            ORDER BY CASE WHEN $8 THEN entry_date END DESC, entry_date
+            LIMIT $9
+            "#,
+            user_id,
+            query.start,
+            query.end,
+            query.has_notes,
+            query.skill_id,
+            query.category,
+            query.cursor,
+            query.sort == SortOrder::Desc,
+            limit,
+        )
+        .fetch_all(&mut transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(diary_entries)
     }
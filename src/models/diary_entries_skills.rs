@@ -1,5 +1,5 @@
-use crate::configuration::{AppData, Environment};
-use crate::models::{DiaryEntry, Skill};
+use crate::configuration::AppData;
+use crate::models::{DiaryEntry, Skill, UnitOfWork};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -9,33 +9,69 @@ pub struct DiaryEntrySkills {
     pub diary_entry_id: i32,
     pub skills_id: i32,
     pub created_at: sqlx::types::chrono::DateTime<Utc>,
+    pub rating: Option<i32>,
+    // Ids of the steps used, for acronym skills like DEAR MAN
+    pub steps: Vec<i32>,
+}
+
+// A skill as rated on a diary entry, for embedding in diary entry responses
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct RatedSkill {
+    pub diary_entry_id: i32,
+    pub skill_id: i32,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub rating: Option<i32>,
+    pub steps: Vec<i32>,
 }
 
 impl DiaryEntrySkills {
-    #[tracing::instrument(name = "Saving diary_entry_skill in the database", skip(config))]
+    #[tracing::instrument(name = "Saving diary_entry_skill in the database", skip(unit_of_work))]
     pub async fn save_diary_entry_skill(
-        config: &AppData,
+        unit_of_work: &mut UnitOfWork<'_>,
         skill: &Skill,
+        rating: i32,
+        steps: &[i32],
         diary_entry: &DiaryEntry,
     ) -> Result<Self, sqlx::Error> {
-        let mut transaction = config.pg_pool.begin().await?;
         let query_statement = r#"
-    INSERT INTO diary_entries_skills (diary_entry_id, skills_id, created_at)
-    VALUES ($1, $2, $3) RETURNING diary_entry_id, skills_id, created_at
+    INSERT INTO diary_entries_skills (diary_entry_id, skills_id, created_at, rating)
+    SELECT id, $2, $3, $5 FROM diary_entries WHERE id = $1 AND user_id = $4
+    RETURNING diary_entry_id, skills_id, created_at, rating, ARRAY[]::integer[] AS steps
     "#;
-        let diary_entry_skill: DiaryEntrySkills = sqlx::query_as(query_statement)
+        let mut diary_entry_skill: DiaryEntrySkills = sqlx::query_as(query_statement)
             .bind(diary_entry.id)
             .bind(skill.id)
             .bind(diary_entry.created_at)
-            .fetch_one(&mut transaction)
+            .bind(diary_entry.user_id)
+            .bind(rating)
+            .fetch_one(unit_of_work.transaction())
             .await
             .map_err(|e| {
                 tracing::error!("Failed to execute query: {:?}", e);
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
+        if !steps.is_empty() {
+            // Ids that aren't steps of this skill are ignored, like unknown skill ids
+            let steps_statement = r#"
+    INSERT INTO diary_entries_skill_steps (diary_entry_id, skill_id, step_id)
+    SELECT $1, $2, id FROM skills WHERE parent_id = $2 AND id = ANY($3) AND deleted_at IS NULL
+    RETURNING step_id
+    "#;
+            let saved_steps: Vec<(i32,)> = sqlx::query_as(steps_statement)
+                .bind(diary_entry.id)
+                .bind(skill.id)
+                .bind(steps)
+                .fetch_all(unit_of_work.transaction())
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to execute query: {:?}", e);
+                    e
+                })?;
+            diary_entry_skill.steps = saved_steps.into_iter().map(|(step_id,)| step_id).collect();
+            diary_entry_skill.steps.sort_unstable();
         }
 
         Ok(diary_entry_skill)
@@ -44,17 +80,30 @@ impl DiaryEntrySkills {
 
 impl DiaryEntrySkills {
     #[tracing::instrument(
-        name = "Retrieving diary_entry_skills by diary entry id from the database",
+        name = "Retrieving diary_entry_skills by diary entry id and user_id from the database",
         skip(config)
     )]
     pub async fn find_diary_entry_skills_by_diary_id(
         config: &AppData,
         diary_entry_id: i32,
+        user_id: &i32,
     ) -> Result<Vec<Self>, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
-        let query_statement = r#"SELECT * from diary_entries_skills WHERE diary_entry_id = $1"#;
+        let query_statement = r#"SELECT diary_entries_skills.diary_entry_id,
+            diary_entries_skills.skills_id,
+            diary_entries_skills.created_at,
+            diary_entries_skills.rating,
+            ARRAY(SELECT step_id FROM diary_entries_skill_steps
+                WHERE diary_entries_skill_steps.diary_entry_id = diary_entries_skills.diary_entry_id
+                AND diary_entries_skill_steps.skill_id = diary_entries_skills.skills_id
+                ORDER BY step_id) AS steps FROM diary_entries_skills
+            JOIN diary_entries
+            ON diary_entries_skills.diary_entry_id = diary_entries.id
+            WHERE diary_entries.id = $1 AND diary_entries.user_id = $2
+            AND diary_entries.deleted_at IS NULL"#;
         let diary_entry_skills: Vec<DiaryEntrySkills> = sqlx::query_as(query_statement)
             .bind(diary_entry_id)
+            .bind(user_id)
             .fetch_all(&mut transaction)
             .await
             .map_err(|e| {
@@ -62,9 +111,7 @@ impl DiaryEntrySkills {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(diary_entry_skills)
     }
@@ -83,10 +130,16 @@ impl DiaryEntrySkills {
         let mut transaction = config.pg_pool.begin().await?;
         let query_statement = r#"SELECT diary_entries_skills.diary_entry_id,
             diary_entries_skills.skills_id,
-            diary_entries_skills.created_at FROM diary_entries_skills
+            diary_entries_skills.created_at,
+            diary_entries_skills.rating,
+            ARRAY(SELECT step_id FROM diary_entries_skill_steps
+                WHERE diary_entries_skill_steps.diary_entry_id = diary_entries_skills.diary_entry_id
+                AND diary_entries_skill_steps.skill_id = diary_entries_skills.skills_id
+                ORDER BY step_id) AS steps FROM diary_entries_skills
             JOIN diary_entries
             ON diary_entries_skills.diary_entry_id = diary_entries.id
-            WHERE diary_entries.entry_date = $1 AND diary_entries.user_id = $2"#;
+            WHERE diary_entries.entry_date = $1 AND diary_entries.user_id = $2
+            AND diary_entries.deleted_at IS NULL"#;
         let diary_entry_skills: Vec<DiaryEntrySkills> = sqlx::query_as(query_statement)
             .bind(diary_entry_date)
             .bind(user_id)
@@ -97,9 +150,7 @@ impl DiaryEntrySkills {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(diary_entry_skills)
     }
@@ -107,32 +158,96 @@ impl DiaryEntrySkills {
 
 impl DiaryEntrySkills {
     #[tracing::instrument(
-        name = "Deleting diary_entry_skills by diary entry id in the database",
-        skip(config)
+        name = "Deleting diary_entry_skills by diary entry id and user_id in the database",
+        skip(unit_of_work)
     )]
     pub async fn delete(
-        config: &AppData,
+        unit_of_work: &mut UnitOfWork<'_>,
         diary_entry: &DiaryEntry,
     ) -> Result<Vec<Self>, sqlx::Error> {
-        let mut transaction = config.pg_pool.begin().await?;
         let query_statement = r#"
     DELETE FROM diary_entries_skills
-    WHERE diary_entry_id = $1
-    RETURNING *;
+    USING diary_entries
+    WHERE diary_entries_skills.diary_entry_id = diary_entries.id
+    AND diary_entries.id = $1 AND diary_entries.user_id = $2
+    RETURNING diary_entries_skills.diary_entry_id,
+    diary_entries_skills.skills_id,
+    diary_entries_skills.created_at,
+    diary_entries_skills.rating,
+    ARRAY(SELECT step_id FROM diary_entries_skill_steps
+        WHERE diary_entries_skill_steps.diary_entry_id = diary_entries_skills.diary_entry_id
+        AND diary_entries_skill_steps.skill_id = diary_entries_skills.skills_id
+        ORDER BY step_id) AS steps;
     "#;
         let diary_entry_skills: Vec<DiaryEntrySkills> = sqlx::query_as(query_statement)
             .bind(diary_entry.id)
-            .fetch_all(&mut transaction)
+            .bind(diary_entry.user_id)
+            .fetch_all(unit_of_work.transaction())
             .await
             .map_err(|e| {
                 tracing::error!("Failed to execute query: {:?}", e);
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        let steps_statement = r#"
+    DELETE FROM diary_entries_skill_steps
+    USING diary_entries
+    WHERE diary_entries_skill_steps.diary_entry_id = diary_entries.id
+    AND diary_entries.id = $1 AND diary_entries.user_id = $2
+    "#;
+        sqlx::query(steps_statement)
+            .bind(diary_entry.id)
+            .bind(diary_entry.user_id)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
 
         Ok(diary_entry_skills)
     }
 }
+
+impl RatedSkill {
+    #[tracing::instrument(
+        name = "Retrieving rated skills by diary entry ids and user_id from the database",
+        skip(config)
+    )]
+    pub async fn find_by_diary_entries(
+        config: &AppData,
+        diary_entry_ids: &[i32],
+        user_id: &i32,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT diary_entries_skills.diary_entry_id,
+            skills.id AS skill_id,
+            skills.name,
+            skills.category,
+            COALESCE(skills.description, '') AS description,
+            diary_entries_skills.rating,
+            ARRAY(SELECT step_id FROM diary_entries_skill_steps
+                WHERE diary_entries_skill_steps.diary_entry_id = diary_entries_skills.diary_entry_id
+                AND diary_entries_skill_steps.skill_id = diary_entries_skills.skills_id
+                ORDER BY step_id) AS steps FROM diary_entries_skills
+            JOIN skills ON skills.id = diary_entries_skills.skills_id
+            JOIN diary_entries
+            ON diary_entries_skills.diary_entry_id = diary_entries.id
+            WHERE diary_entries.id = ANY($1) AND diary_entries.user_id = $2
+            AND diary_entries.deleted_at IS NULL
+            ORDER BY diary_entries_skills.diary_entry_id, skills.id"#;
+        let rated_skills: Vec<RatedSkill> = sqlx::query_as(query_statement)
+            .bind(diary_entry_ids)
+            .bind(user_id)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(rated_skills)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::configuration::AppData;
+use crate::models::UnitOfWork;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+
+// How long a verification link can be used for
+pub const VERIFICATION_LINK_LIFETIME_HOURS: i64 = 48;
+
+/// The token in an email verification link: `<user id>.<expiry>.<signature>`.
+/// The signature covers the address too, so a link stops working if the
+/// user's email changes before it is followed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailVerification {
+    pub user_id: i32,
+    // Unix timestamp the link expires at
+    pub expires_at: i64,
+}
+
+// The app secret also signs session cookies, so links are signed with a key derived from it
+fn signing_key(secret: &Secret<String>) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose_secret().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(b"email-verification");
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl EmailVerification {
+    pub fn new(user_id: i32) -> Self {
+        Self {
+            user_id,
+            expires_at: (Utc::now() + Duration::hours(VERIFICATION_LINK_LIFETIME_HOURS))
+                .timestamp(),
+        }
+    }
+
+    fn mac(&self, email: &str, secret: &Secret<String>) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&signing_key(secret))
+            .expect("HMAC accepts keys of any length");
+        mac.update(format!("{}.{}.{}", self.user_id, self.expires_at, email).as_bytes());
+        mac
+    }
+
+    pub fn token(&self, email: &str, secret: &Secret<String>) -> String {
+        let signature = hex::encode(self.mac(email, secret).finalize().into_bytes());
+        format!("{}.{}.{}", self.user_id, self.expires_at, signature)
+    }
+
+    // Splits a token without checking it, so the user's address can be looked up
+    fn parse(token: &str) -> Option<(Self, Vec<u8>)> {
+        let mut parts = token.splitn(3, '.');
+        let user_id = parts.next()?.parse().ok()?;
+        let expires_at = parts.next()?.parse().ok()?;
+        let signature = hex::decode(parts.next()?).ok()?;
+        Some((
+            Self {
+                user_id,
+                expires_at,
+            },
+            signature,
+        ))
+    }
+
+    /// The user a token was issued to, when it is well formed, unexpired and
+    /// signed for the address they have now.
+    #[tracing::instrument(
+        name = "Checking email verification token",
+        skip(config, token, secret)
+    )]
+    pub async fn check(
+        config: &AppData,
+        token: &str,
+        secret: &Secret<String>,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let (verification, signature) = match Self::parse(token) {
+            Some(parsed) => parsed,
+            None => return Ok(None),
+        };
+        if verification.expires_at < Utc::now().timestamp() {
+            return Ok(None);
+        }
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT email FROM users WHERE id = $1"#;
+        let email: Option<(String,)> = sqlx::query_as(query_statement)
+            .bind(verification.user_id)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+        transaction.commit().await?;
+
+        // Unknown ids fail the same way as bad signatures
+        let email = match email {
+            Some((email,)) => email,
+            None => return Ok(None),
+        };
+        let signed = verification
+            .mac(&email, secret)
+            .verify_slice(&signature)
+            .is_ok();
+        Ok(signed.then_some(verification.user_id))
+    }
+}
+
+impl EmailVerification {
+    #[tracing::instrument(name = "Marking email as verified", skip(unit_of_work))]
+    pub async fn mark_verified(
+        unit_of_work: &mut UnitOfWork<'_>,
+        user_id: &i32,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    UPDATE users SET email_verified_at = $2
+    WHERE id = $1 AND email_verified_at IS NULL
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
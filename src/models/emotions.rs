@@ -0,0 +1,153 @@
+use crate::configuration::AppData;
+use crate::models::{DiaryEntry, UnitOfWork};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct Emotion {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct DiaryEntryEmotion {
+    pub diary_entry_id: i32,
+    pub emotion_id: i32,
+    pub name: String,
+    pub intensity: i32,
+}
+
+impl Emotion {
+    #[tracing::instrument(name = "Retrieving all emotions from the database", skip(config))]
+    pub async fn find_all(config: &AppData) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT id, name FROM emotions ORDER BY id"#;
+        let emotions: Vec<Emotion> = sqlx::query_as(query_statement)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(emotions)
+    }
+
+    #[tracing::instrument(
+        name = "Retrieving emotions by ids from the database",
+        skip(unit_of_work)
+    )]
+    pub async fn find_by_ids(
+        unit_of_work: &mut UnitOfWork<'_>,
+        emotion_ids: &[i32],
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let query_statement = r#"SELECT id, name FROM emotions WHERE id = ANY($1)"#;
+        let emotions: Vec<Emotion> = sqlx::query_as(query_statement)
+            .bind(emotion_ids)
+            .fetch_all(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(emotions)
+    }
+}
+
+impl DiaryEntryEmotion {
+    #[tracing::instrument(
+        name = "Saving diary_entry_emotion in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn save(
+        unit_of_work: &mut UnitOfWork<'_>,
+        emotion: &Emotion,
+        intensity: i32,
+        diary_entry: &DiaryEntry,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    INSERT INTO diary_entries_emotions (diary_entry_id, emotion_id, intensity, created_at)
+    SELECT id, $2, $3, $4 FROM diary_entries WHERE id = $1 AND user_id = $5
+    RETURNING diary_entry_id, emotion_id, $6::text AS name, intensity
+    "#;
+        let diary_entry_emotion: DiaryEntryEmotion = sqlx::query_as(query_statement)
+            .bind(diary_entry.id)
+            .bind(emotion.id)
+            .bind(intensity)
+            .bind(Utc::now())
+            .bind(diary_entry.user_id)
+            .bind(&emotion.name)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(diary_entry_emotion)
+    }
+
+    #[tracing::instrument(
+        name = "Retrieving diary_entry_emotions by diary entry id and user_id from the database",
+        skip(config)
+    )]
+    pub async fn find_by_diary_entry(
+        config: &AppData,
+        diary_entry_id: i32,
+        user_id: &i32,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT diary_entries_emotions.diary_entry_id,
+            diary_entries_emotions.emotion_id,
+            emotions.name,
+            diary_entries_emotions.intensity FROM diary_entries_emotions
+            JOIN emotions ON diary_entries_emotions.emotion_id = emotions.id
+            JOIN diary_entries ON diary_entries_emotions.diary_entry_id = diary_entries.id
+            WHERE diary_entries.id = $1 AND diary_entries.user_id = $2
+            ORDER BY emotions.id"#;
+        let diary_entry_emotions: Vec<DiaryEntryEmotion> = sqlx::query_as(query_statement)
+            .bind(diary_entry_id)
+            .bind(user_id)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(diary_entry_emotions)
+    }
+
+    #[tracing::instrument(
+        name = "Deleting diary_entry_emotions by diary entry id and user_id in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn delete(
+        unit_of_work: &mut UnitOfWork<'_>,
+        diary_entry: &DiaryEntry,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    DELETE FROM diary_entries_emotions
+    USING diary_entries
+    WHERE diary_entries_emotions.diary_entry_id = diary_entries.id
+    AND diary_entries.id = $1 AND diary_entries.user_id = $2
+    "#;
+        sqlx::query(query_statement)
+            .bind(diary_entry.id)
+            .bind(diary_entry.user_id)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
@@ -1,17 +1,55 @@
 use async_trait::async_trait;
 
 pub mod credentials;
+pub mod diary_cards;
 pub mod diary_entries;
 pub mod diary_entries_skills;
+pub mod email_verification;
+pub mod emotions;
+pub mod password_reset_tokens;
+pub mod skill_categories;
+pub mod skill_preferences;
 pub mod skills;
+pub mod stats;
+pub mod streaks;
+pub mod unit_of_work;
+pub mod urges;
+pub mod user_settings;
 
 pub use credentials::*;
+pub use diary_cards::*;
 pub use diary_entries::*;
 pub use diary_entries_skills::*;
+pub use email_verification::*;
+pub use emotions::*;
+pub use password_reset_tokens::*;
+pub use skill_categories::*;
+pub use skill_preferences::*;
 pub use skills::*;
+pub use stats::*;
+pub use streaks::*;
+pub use unit_of_work::*;
+pub use urges::*;
+pub use user_settings::*;
 
 use crate::configuration::AppData;
 
+// True when a write referenced a row that doesn't exist, such as an unknown skill category
+pub fn is_foreign_key_violation(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e.code().as_deref() == Some("23503"),
+        _ => false,
+    }
+}
+
+// True when a write would duplicate a unique key, such as a global skill's name
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e.code().as_deref() == Some("23505"),
+        _ => false,
+    }
+}
+
 #[async_trait]
 pub trait Record {
     async fn save(self, pool: &AppData) -> Result<Self, sqlx::Error>
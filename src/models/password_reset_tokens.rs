@@ -0,0 +1,95 @@
+use crate::models::UnitOfWork;
+use chrono::{Duration, Utc};
+use rand::Rng;
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+
+// How long a forgot password link can be used for
+pub const RESET_TOKEN_LIFETIME_MINUTES: i64 = 60;
+
+/// A single use token emailed to a user who forgot their password.
+/// Only the token's sha256 is stored.
+pub struct PasswordResetToken;
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl PasswordResetToken {
+    #[tracing::instrument(name = "Issuing password reset token", skip(unit_of_work))]
+    pub async fn issue(
+        unit_of_work: &mut UnitOfWork<'_>,
+        user_id: &i32,
+    ) -> Result<Secret<String>, sqlx::Error> {
+        let token = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let now = Utc::now();
+        let query_statement = r#"
+    INSERT INTO password_reset_tokens (user_id, token_hash, expires_at, created_at)
+    VALUES ($1, $2, $3, $4)
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(hash_token(&token))
+            .bind(now + Duration::minutes(RESET_TOKEN_LIFETIME_MINUTES))
+            .bind(now)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(Secret::new(token))
+    }
+}
+
+impl PasswordResetToken {
+    // Uses up the token, returning whose it was. None when it is unknown, used or expired.
+    #[tracing::instrument(name = "Redeeming password reset token", skip(unit_of_work, token))]
+    pub async fn redeem(
+        unit_of_work: &mut UnitOfWork<'_>,
+        token: &Secret<String>,
+    ) -> Result<Option<i32>, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE password_reset_tokens SET used_at = $2
+    WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+    RETURNING user_id
+    "#;
+        let user_id: Option<(i32,)> = sqlx::query_as(query_statement)
+            .bind(hash_token(token.expose_secret()))
+            .bind(Utc::now())
+            .fetch_optional(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(user_id.map(|(user_id,)| user_id))
+    }
+}
+
+impl PasswordResetToken {
+    // Once the password has changed, links sent before the change stop working
+    #[tracing::instrument(name = "Revoking password reset tokens", skip(unit_of_work))]
+    pub async fn revoke_all(
+        unit_of_work: &mut UnitOfWork<'_>,
+        user_id: &i32,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    UPDATE password_reset_tokens SET used_at = $2
+    WHERE user_id = $1 AND used_at IS NULL
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::configuration::AppData;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// A DBT module the diary card groups skills by
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct SkillCategory {
+    pub name: String,
+    pub label: String,
+    pub color: String,
+    pub sort_order: i32,
+    pub description: String,
+}
+
+impl SkillCategory {
+    #[tracing::instrument(
+        name = "Retrieving all skill categories from the database",
+        skip(config)
+    )]
+    pub async fn find_all(config: &AppData) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"
+    SELECT name, label, color, sort_order, description FROM skill_categories
+    ORDER BY sort_order, name
+    "#;
+        let categories: Vec<SkillCategory> = sqlx::query_as(query_statement)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(categories)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::models::UnitOfWork;
+
+// A user's hidden flag and card position for a skill
+pub struct SkillPreference;
+
+impl SkillPreference {
+    #[tracing::instrument(name = "Saving skill hidden flag in the database", skip(unit_of_work))]
+    pub async fn set_hidden(
+        unit_of_work: &mut UnitOfWork<'_>,
+        skill_id: i32,
+        hidden: bool,
+        user_id: &i32,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    INSERT INTO user_skill_preferences (user_id, skill_id, hidden)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (user_id, skill_id) DO UPDATE SET hidden = EXCLUDED.hidden
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(skill_id)
+            .bind(hidden)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+
+    // Positions skills in the order given; skills left out go back to the end of the card
+    #[tracing::instrument(name = "Saving skill order in the database", skip(unit_of_work))]
+    pub async fn reorder(
+        unit_of_work: &mut UnitOfWork<'_>,
+        skill_ids: &[i32],
+        user_id: &i32,
+    ) -> Result<(), sqlx::Error> {
+        let clear_statement = r#"
+    UPDATE user_skill_preferences SET position = NULL WHERE user_id = $1
+    "#;
+        sqlx::query(clear_statement)
+            .bind(user_id)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        let query_statement = r#"
+    INSERT INTO user_skill_preferences (user_id, skill_id, position)
+    SELECT $1, skill_id, position::integer
+    FROM UNNEST($2::integer[]) WITH ORDINALITY AS skill_order(skill_id, position)
+    ON CONFLICT (user_id, skill_id) DO UPDATE SET position = EXCLUDED.position
+    "#;
+        sqlx::query(query_statement)
+            .bind(user_id)
+            .bind(skill_ids)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
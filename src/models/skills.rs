@@ -1,6 +1,7 @@
-use crate::configuration::{AppData, Environment};
-use crate::models::Record;
+use crate::configuration::AppData;
+use crate::models::{DiaryEntry, Record, UnitOfWork};
 use async_trait::async_trait;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -10,6 +11,18 @@ pub struct Skill {
     pub name: String,
     pub category: String,
     pub description: String,
+    // None for skills in the global catalogue, otherwise the user the skill is private to
+    pub user_id: Option<i32>,
+    // Set for the steps of an acronym skill such as DEAR MAN
+    pub parent_id: Option<i32>,
+}
+
+// A skill with its steps, for acronym skills like DEAR MAN, GIVE and FAST
+#[derive(Serialize, Debug)]
+pub struct SkillTree {
+    #[serde(flatten)]
+    pub skill: Skill,
+    pub steps: Vec<Skill>,
 }
 
 #[async_trait]
@@ -18,14 +31,15 @@ impl Record for Skill {
     async fn save(self, config: &AppData) -> Result<Self, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
         let query_statement = r#"
-    INSERT INTO skills (id, name, category, description)
-    VALUES ($1, $2, $3) RETURNING id, name, category, description
+    INSERT INTO skills (name, category, description, user_id, parent_id)
+    VALUES ($1, $2, $3, $4, $5) RETURNING id, name, category, description, user_id, parent_id
     "#;
         let query: Skill = sqlx::query_as(query_statement)
-            .bind(self.id)
             .bind(self.name)
             .bind(self.category)
             .bind(self.description)
+            .bind(self.user_id)
+            .bind(self.parent_id)
             .fetch_one(&mut transaction)
             .await
             .map_err(|e| {
@@ -33,9 +47,7 @@ impl Record for Skill {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(query)
     }
@@ -53,32 +65,89 @@ impl Record for Skill {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(skill)
     }
 }
 
 impl Skill {
-    #[tracing::instrument(name = "Retrieving skills by ids from the database", skip(config))]
+    // Only returns skills visible to the user: global ones and their own private ones.
+    // Steps are logged through their parent skill, so they are left out.
+    #[tracing::instrument(
+        name = "Retrieving skills by ids from the database",
+        skip(unit_of_work)
+    )]
     pub async fn find_by_ids(
-        config: &AppData,
+        unit_of_work: &mut UnitOfWork<'_>,
         skill_ids: &[i32],
+        user_id: &i32,
     ) -> Result<Vec<Self>, sqlx::Error> {
+        let skills: Vec<Skill> = sqlx::query_as!(
+            Skill,
+            r#"
+            SELECT id, name, category, description as "description!", user_id, parent_id
+            FROM skills
+            WHERE id = ANY($1) AND (user_id IS NULL OR user_id = $2) AND deleted_at IS NULL
+            AND parent_id IS NULL
+            "#,
+            skill_ids,
+            user_id,
+        )
+        .fetch_all(unit_of_work.transaction())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+        Ok(skills)
+    }
+
+    // Same as find_by_ids, except retired skills the entry is already rated with
+    // are kept, so saving an old entry again doesn't drop them.
+    #[tracing::instrument(
+        name = "Retrieving skills by ids for a diary entry from the database",
+        skip(unit_of_work, diary_entry)
+    )]
+    pub async fn find_by_ids_for_entry(
+        unit_of_work: &mut UnitOfWork<'_>,
+        skill_ids: &[i32],
+        diary_entry: &DiaryEntry,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let skills: Vec<Skill> = sqlx::query_as!(
+            Skill,
+            r#"
+            SELECT id, name, category, description as "description!", user_id, parent_id
+            FROM skills
+            WHERE id = ANY($1) AND (user_id IS NULL OR user_id = $2) AND parent_id IS NULL
+            AND (deleted_at IS NULL OR id IN (
+                SELECT skills_id FROM diary_entries_skills WHERE diary_entry_id = $3
+            ))
+            "#,
+            skill_ids,
+            diary_entry.user_id,
+            diary_entry.id,
+        )
+        .fetch_all(unit_of_work.transaction())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+        Ok(skills)
+    }
+}
+
+impl Skill {
+    #[tracing::instrument(name = "Retrieving all skills from the database", skip(config))]
+    pub async fn find_all(config: &AppData) -> Result<Vec<Self>, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
-        let list = skill_ids
-            .iter()
-            .fold(String::new(), |str: String, item| -> String {
-                if str.is_empty() {
-                    format!("'{}'", item)
-                } else {
-                    format!("{}, '{}'", str, item)
-                }
-            });
-        let query_statement = format!("SELECT * from skills WHERE id IN ({});", list);
-        let skills: Vec<Skill> = sqlx::query_as(&query_statement)
+        let query_statement = r#"
+    SELECT * from skills WHERE deleted_at IS NULL
+    "#;
+        let skills: Vec<Skill> = sqlx::query_as(query_statement)
             .fetch_all(&mut transaction)
             .await
             .map_err(|e| {
@@ -86,22 +155,70 @@ impl Skill {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(skills)
     }
 }
 
 impl Skill {
-    #[tracing::instrument(name = "Retrieving all skills from the database", skip(config))]
-    pub async fn find_all(config: &AppData) -> Result<Vec<Self>, sqlx::Error> {
+    #[tracing::instrument(
+        name = "Retrieving skill by id and user_id from the database",
+        skip(config)
+    )]
+    pub async fn find_visible_by_id(
+        config: &AppData,
+        id: i32,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
         let mut transaction = config.pg_pool.begin().await?;
         let query_statement = r#"
     SELECT * from skills
+    WHERE id = $1 AND (user_id IS NULL OR user_id = $2) AND deleted_at IS NULL
+    "#;
+        let skill: Skill = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(skill)
+    }
+}
+
+impl Skill {
+    // The user's diary card: global and private skills minus the hidden ones,
+    // in the user's order with anything not yet ordered at the end.
+    // Narrowed to one category when given.
+    #[tracing::instrument(
+        name = "Retrieving a user's effective skill list from the database",
+        skip(config)
+    )]
+    pub async fn find_for_user(
+        config: &AppData,
+        user_id: &i32,
+        category: Option<&str>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"
+    SELECT skills.* from skills
+    LEFT JOIN user_skill_preferences
+    ON user_skill_preferences.skill_id = skills.id AND user_skill_preferences.user_id = $1
+    WHERE (skills.user_id IS NULL OR skills.user_id = $1) AND skills.deleted_at IS NULL
+    AND skills.parent_id IS NULL
+    AND NOT COALESCE(user_skill_preferences.hidden, false)
+    AND ($2::text IS NULL OR skills.category = $2)
+    ORDER BY user_skill_preferences.position NULLS LAST, skills.id
     "#;
         let skills: Vec<Skill> = sqlx::query_as(query_statement)
+            .bind(user_id)
+            .bind(category)
             .fetch_all(&mut transaction)
             .await
             .map_err(|e| {
@@ -109,10 +226,115 @@ impl Skill {
                 e
             })?;
 
-        if let Environment::Dev = config.env {
-            transaction.commit().await?;
-        }
+        transaction.commit().await?;
 
         Ok(skills)
     }
 }
+
+// Changes to the global catalogue. Private skills and retired skills are left alone,
+// so each of these returns RowNotFound for them.
+impl Skill {
+    #[tracing::instrument(name = "Updating global skill in the database", skip(unit_of_work))]
+    pub async fn update_global(
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: i32,
+        name: &str,
+        category: &str,
+        description: &str,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE skills SET name = $2, category = $3, description = $4
+    WHERE id = $1 AND user_id IS NULL AND deleted_at IS NULL
+    RETURNING id, name, category, description, user_id, parent_id
+    "#;
+        let skill: Skill = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(name)
+            .bind(category)
+            .bind(description)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(skill)
+    }
+
+    #[tracing::instrument(
+        name = "Recategorizing global skill in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn recategorize(
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: i32,
+        category: &str,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE skills SET category = $2
+    WHERE id = $1 AND user_id IS NULL AND deleted_at IS NULL
+    RETURNING id, name, category, description, user_id, parent_id
+    "#;
+        let skill: Skill = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(category)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(skill)
+    }
+
+    #[tracing::instrument(
+        name = "Soft deleting global skill in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn soft_delete(
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: i32,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE skills SET deleted_at = $2
+    WHERE id = $1 AND user_id IS NULL AND deleted_at IS NULL
+    RETURNING id, name, category, description, user_id, parent_id
+    "#;
+        let skill: Skill = sqlx::query_as(query_statement)
+            .bind(id)
+            .bind(Utc::now())
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(skill)
+    }
+}
+
+impl Skill {
+    #[tracing::instrument(name = "Retrieving skill steps from the database", skip(config))]
+    pub async fn find_steps(config: &AppData, parent_id: i32) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"
+    SELECT * from skills WHERE parent_id = $1 AND deleted_at IS NULL ORDER BY id
+    "#;
+        let steps: Vec<Skill> = sqlx::query_as(query_statement)
+            .bind(parent_id)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(steps)
+    }
+}
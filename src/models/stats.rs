@@ -0,0 +1,154 @@
+use crate::configuration::AppData;
+use crate::models::DateRangeRequest;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// How many skills `most_used` and `least_used` list.
+pub const RANKED_SKILLS: usize = 5;
+
+/// Skill usage over a date range, for progress charts.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Stats {
+    pub start: Option<sqlx::types::chrono::NaiveDate>,
+    pub end: Option<sqlx::types::chrono::NaiveDate>,
+    pub entries: i64,
+    pub skills: Vec<SkillStats>,
+    pub categories: Vec<CategoryStats>,
+    pub most_used: Vec<SkillStats>,
+    pub least_used: Vec<SkillStats>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, FromRow)]
+pub struct SkillStats {
+    pub skill_id: i32,
+    pub name: String,
+    pub category: String,
+    pub uses: i64,
+    // None when the skill was never rated
+    pub average_rating: Option<f64>,
+    // Most consecutive days the skill was logged
+    pub longest_streak: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct CategoryStats {
+    pub category: String,
+    pub label: String,
+    pub uses: i64,
+    pub average_rating: Option<f64>,
+}
+
+impl Stats {
+    #[tracing::instrument(name = "Computing skill statistics for a user", skip(config))]
+    pub async fn for_user(
+        config: &AppData,
+        date_range: DateRangeRequest,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let entries_statement = r#"
+    SELECT count(*) FROM diary_entries
+    WHERE user_id = $1 AND deleted_at IS NULL
+    AND ($2::date IS NULL OR entry_date >= $2)
+    AND ($3::date IS NULL OR entry_date <= $3)
+    "#;
+        let (entries,): (i64,) = sqlx::query_as(entries_statement)
+            .bind(user_id)
+            .bind(date_range.start)
+            .bind(date_range.end)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        // Every skill on the user's card, used or not, plus anything used that has since
+        // been hidden or retired. Consecutive days share an island: entry_date minus the
+        // skill's running day count is the same for each day of a run.
+        let skills_statement = r#"
+    WITH uses AS (
+        SELECT diary_entries_skills.skills_id, diary_entries.entry_date, diary_entries_skills.rating
+        FROM diary_entries_skills
+        JOIN diary_entries ON diary_entries.id = diary_entries_skills.diary_entry_id
+        WHERE diary_entries.user_id = $1 AND diary_entries.deleted_at IS NULL
+        AND ($2::date IS NULL OR diary_entries.entry_date >= $2)
+        AND ($3::date IS NULL OR diary_entries.entry_date <= $3)
+    ), runs AS (
+        SELECT skills_id, count(*) AS days FROM (
+            SELECT skills_id,
+            entry_date - (ROW_NUMBER() OVER (PARTITION BY skills_id ORDER BY entry_date))::int AS island
+            FROM uses
+        ) AS islands
+        GROUP BY skills_id, island
+    )
+    SELECT skills.id AS skill_id, skills.name, skills.category,
+    (SELECT count(*) FROM uses WHERE uses.skills_id = skills.id) AS uses,
+    (SELECT avg(rating)::float8 FROM uses WHERE uses.skills_id = skills.id) AS average_rating,
+    (SELECT COALESCE(max(days), 0) FROM runs WHERE runs.skills_id = skills.id) AS longest_streak
+    FROM skills
+    LEFT JOIN user_skill_preferences
+    ON user_skill_preferences.skill_id = skills.id AND user_skill_preferences.user_id = $1
+    WHERE skills.id IN (SELECT skills_id FROM uses)
+    OR ((skills.user_id IS NULL OR skills.user_id = $1) AND skills.deleted_at IS NULL
+        AND skills.parent_id IS NULL AND NOT COALESCE(user_skill_preferences.hidden, false))
+    ORDER BY uses DESC, skills.id
+    "#;
+        let skills: Vec<SkillStats> = sqlx::query_as(skills_statement)
+            .bind(user_id)
+            .bind(date_range.start)
+            .bind(date_range.end)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        let categories_statement = r#"
+    SELECT skill_categories.name AS category, skill_categories.label,
+    count(uses.skills_id) AS uses, avg(uses.rating)::float8 AS average_rating
+    FROM skill_categories
+    LEFT JOIN (
+        SELECT diary_entries_skills.skills_id, skills.category, diary_entries_skills.rating
+        FROM diary_entries_skills
+        JOIN diary_entries ON diary_entries.id = diary_entries_skills.diary_entry_id
+        JOIN skills ON skills.id = diary_entries_skills.skills_id
+        WHERE diary_entries.user_id = $1 AND diary_entries.deleted_at IS NULL
+        AND ($2::date IS NULL OR diary_entries.entry_date >= $2)
+        AND ($3::date IS NULL OR diary_entries.entry_date <= $3)
+    ) AS uses ON uses.category = skill_categories.name
+    GROUP BY skill_categories.name
+    ORDER BY skill_categories.sort_order
+    "#;
+        let categories: Vec<CategoryStats> = sqlx::query_as(categories_statement)
+            .bind(user_id)
+            .bind(date_range.start)
+            .bind(date_range.end)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        let most_used = skills
+            .iter()
+            .filter(|skill| skill.uses > 0)
+            .take(RANKED_SKILLS)
+            .cloned()
+            .collect();
+        let least_used = skills.iter().rev().take(RANKED_SKILLS).cloned().collect();
+        Ok(Stats {
+            start: date_range.start,
+            end: date_range.end,
+            entries,
+            skills,
+            categories,
+            most_used,
+            least_used,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use crate::configuration::AppData;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// How consistently a user fills in their diary card, counted in days of their own timezone.
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct Streaks {
+    // The user's current date, which days are counted up to
+    pub today: sqlx::types::chrono::NaiveDate,
+    // Consecutive days up to today, or up to yesterday while today is still unfilled
+    pub current: i64,
+    pub longest: i64,
+    // Share of the last 30 and 90 days, including today, with an entry
+    pub completion_30_days: f64,
+    pub completion_90_days: f64,
+}
+
+impl Streaks {
+    #[tracing::instrument(name = "Computing logging streaks for a user", skip(config))]
+    pub async fn for_user(config: &AppData, user_id: &i32) -> Result<Self, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        // Consecutive days share an island: entry_date minus the running day count
+        let query_statement = r#"
+    WITH today AS (
+        SELECT (now() AT TIME ZONE timezone)::date AS today FROM users WHERE id = $1
+    ), days AS (
+        SELECT DISTINCT entry_date FROM diary_entries
+        WHERE user_id = $1 AND deleted_at IS NULL AND entry_date <= (SELECT today FROM today)
+    ), runs AS (
+        SELECT max(entry_date) AS last_day, count(*) AS days FROM (
+            SELECT entry_date, entry_date - (ROW_NUMBER() OVER (ORDER BY entry_date))::int AS island
+            FROM days
+        ) AS islands
+        GROUP BY island
+    )
+    SELECT today,
+    COALESCE((SELECT days FROM runs WHERE last_day >= today - 1), 0) AS current,
+    COALESCE((SELECT max(days) FROM runs), 0) AS longest,
+    (SELECT count(*) FROM days WHERE entry_date > today - 30)::float8 / 30 AS completion_30_days,
+    (SELECT count(*) FROM days WHERE entry_date > today - 90)::float8 / 90 AS completion_90_days
+    FROM today
+    "#;
+        let streaks: Streaks = sqlx::query_as(query_statement)
+            .bind(user_id)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(streaks)
+    }
+}
@@ -0,0 +1,28 @@
+use crate::configuration::AppData;
+use sqlx::{Postgres, Transaction};
+
+/// Groups several model writes into a single database transaction.
+/// Nothing is persisted until `commit` is called; dropping the unit of work
+/// rolls back everything written through it.
+pub struct UnitOfWork<'c> {
+    transaction: Transaction<'c, Postgres>,
+}
+
+impl UnitOfWork<'static> {
+    #[tracing::instrument(name = "Beginning unit of work", skip(config))]
+    pub async fn begin(config: &AppData) -> Result<Self, sqlx::Error> {
+        let transaction = config.pg_pool.begin().await?;
+        Ok(UnitOfWork { transaction })
+    }
+}
+
+impl<'c> UnitOfWork<'c> {
+    pub fn transaction(&mut self) -> &mut Transaction<'c, Postgres> {
+        &mut self.transaction
+    }
+
+    #[tracing::instrument(name = "Committing unit of work", skip(self))]
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.transaction.commit().await
+    }
+}
@@ -0,0 +1,147 @@
+use crate::configuration::AppData;
+use crate::models::{DiaryEntry, UnitOfWork};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct Urge {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow)]
+pub struct DiaryEntryUrge {
+    pub diary_entry_id: i32,
+    pub urge_id: i32,
+    pub name: String,
+    pub intensity: i32,
+}
+
+impl Urge {
+    #[tracing::instrument(name = "Retrieving all urges from the database", skip(config))]
+    pub async fn find_all(config: &AppData) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT id, name FROM urges ORDER BY id"#;
+        let urges: Vec<Urge> = sqlx::query_as(query_statement)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(urges)
+    }
+
+    #[tracing::instrument(name = "Retrieving urges by ids from the database", skip(unit_of_work))]
+    pub async fn find_by_ids(
+        unit_of_work: &mut UnitOfWork<'_>,
+        urge_ids: &[i32],
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let query_statement = r#"SELECT id, name FROM urges WHERE id = ANY($1)"#;
+        let urges: Vec<Urge> = sqlx::query_as(query_statement)
+            .bind(urge_ids)
+            .fetch_all(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(urges)
+    }
+}
+
+impl DiaryEntryUrge {
+    #[tracing::instrument(name = "Saving diary_entry_urge in the database", skip(unit_of_work))]
+    pub async fn save(
+        unit_of_work: &mut UnitOfWork<'_>,
+        urge: &Urge,
+        intensity: i32,
+        diary_entry: &DiaryEntry,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    INSERT INTO diary_entries_urges (diary_entry_id, urge_id, intensity, created_at)
+    SELECT id, $2, $3, $4 FROM diary_entries WHERE id = $1 AND user_id = $5
+    RETURNING diary_entry_id, urge_id, $6::text AS name, intensity
+    "#;
+        let diary_entry_urge: DiaryEntryUrge = sqlx::query_as(query_statement)
+            .bind(diary_entry.id)
+            .bind(urge.id)
+            .bind(intensity)
+            .bind(Utc::now())
+            .bind(diary_entry.user_id)
+            .bind(&urge.name)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(diary_entry_urge)
+    }
+
+    #[tracing::instrument(
+        name = "Retrieving diary_entry_urges by diary entry id and user_id from the database",
+        skip(config)
+    )]
+    pub async fn find_by_diary_entry(
+        config: &AppData,
+        diary_entry_id: i32,
+        user_id: &i32,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT diary_entries_urges.diary_entry_id,
+            diary_entries_urges.urge_id,
+            urges.name,
+            diary_entries_urges.intensity FROM diary_entries_urges
+            JOIN urges ON diary_entries_urges.urge_id = urges.id
+            JOIN diary_entries ON diary_entries_urges.diary_entry_id = diary_entries.id
+            WHERE diary_entries.id = $1 AND diary_entries.user_id = $2
+            ORDER BY urges.id"#;
+        let diary_entry_urges: Vec<DiaryEntryUrge> = sqlx::query_as(query_statement)
+            .bind(diary_entry_id)
+            .bind(user_id)
+            .fetch_all(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(diary_entry_urges)
+    }
+
+    #[tracing::instrument(
+        name = "Deleting diary_entry_urges by diary entry id and user_id in the database",
+        skip(unit_of_work)
+    )]
+    pub async fn delete(
+        unit_of_work: &mut UnitOfWork<'_>,
+        diary_entry: &DiaryEntry,
+    ) -> Result<(), sqlx::Error> {
+        let query_statement = r#"
+    DELETE FROM diary_entries_urges
+    USING diary_entries
+    WHERE diary_entries_urges.diary_entry_id = diary_entries.id
+    AND diary_entries.id = $1 AND diary_entries.user_id = $2
+    "#;
+        sqlx::query(query_statement)
+            .bind(diary_entry.id)
+            .bind(diary_entry.user_id)
+            .execute(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
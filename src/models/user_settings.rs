@@ -0,0 +1,93 @@
+use crate::configuration::AppData;
+use crate::models::UnitOfWork;
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Weekdays as stored in `users.week_start`, in ISO order.
+pub const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, FromRow)]
+pub struct UserSettings {
+    pub week_start: String,
+    pub timezone: String,
+}
+
+impl UserSettings {
+    pub fn week_start_day(&self) -> Weekday {
+        self.week_start.parse().unwrap_or(Weekday::Mon)
+    }
+}
+
+impl UserSettings {
+    #[tracing::instrument(name = "Retrieving user settings from the database", skip(config))]
+    pub async fn find(config: &AppData, user_id: &i32) -> Result<Self, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT week_start, timezone FROM users WHERE id = $1"#;
+        let settings: UserSettings = sqlx::query_as(query_statement)
+            .bind(user_id)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(settings)
+    }
+
+    #[tracing::instrument(name = "Updating user settings in the database", skip(unit_of_work))]
+    pub async fn update(
+        &self,
+        unit_of_work: &mut UnitOfWork<'_>,
+        user_id: &i32,
+    ) -> Result<Self, sqlx::Error> {
+        let query_statement = r#"
+    UPDATE users SET week_start = $1, timezone = $2 WHERE id = $3
+    RETURNING week_start, timezone
+    "#;
+        let settings: UserSettings = sqlx::query_as(query_statement)
+            .bind(&self.week_start)
+            .bind(&self.timezone)
+            .bind(user_id)
+            .fetch_one(unit_of_work.transaction())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        Ok(settings)
+    }
+}
+
+impl UserSettings {
+    // Postgres is the source of timezone names, since days are counted in SQL
+    #[tracing::instrument(name = "Checking a timezone name against the database", skip(config))]
+    pub async fn is_known_timezone(config: &AppData, timezone: &str) -> Result<bool, sqlx::Error> {
+        let mut transaction = config.pg_pool.begin().await?;
+        let query_statement = r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)"#;
+        let (known,): (bool,) = sqlx::query_as(query_statement)
+            .bind(timezone)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(known)
+    }
+}
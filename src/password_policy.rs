@@ -0,0 +1,268 @@
+use anyhow::Context;
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+// Characters of a SHA-1 hash that pick the range it is looked up in
+const PREFIX_LENGTH: usize = 5;
+
+// Passwords people pick most often, matched after undoing common l33t substitutions
+const COMMON_WORDS: &[&str] = &[
+    "password", "qwerty", "letmein", "welcome", "admin", "dragon", "monkey", "iloveyou",
+    "sunshine", "princess", "football", "baseball", "master", "shadow", "superman", "trustno",
+    "secret", "login", "hello", "freedom", "whatever", "passw",
+];
+
+// Keyboard rows, for spotting runs like "qwer" or "asdf"
+const KEYBOARD_ROWS: &[&str] = &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Known-breached passwords, as SHA-1 hashes grouped by their first five characters.
+/// A password is checked by looking up the range for its hash's prefix and
+/// searching it for the rest, the same k-anonymity scheme as Have I Been Pwned.
+#[derive(Debug, Default)]
+pub struct BreachedPasswords {
+    ranges: HashMap<String, HashSet<String>>,
+}
+
+impl BreachedPasswords {
+    /// Reads a file of upper case SHA-1 hashes, one per line. Anything after a
+    /// colon, such as a breach count, is ignored, as are blank and `#` lines.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let text = std::fs::read_to_string(path).with_context(|| {
+            format!("Unable to read breached passwords from {}", path.display())
+        })?;
+        let mut breached = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let hash = line.split(':').next().unwrap_or_default().to_uppercase();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                anyhow::bail!(
+                    "{} line {}: expected a SHA-1 hash",
+                    path.display(),
+                    index + 1
+                );
+            }
+            let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+            breached
+                .ranges
+                .entry(prefix.to_string())
+                .or_default()
+                .insert(suffix.to_string());
+        }
+        Ok(breached)
+    }
+
+    // The suffixes of every breached hash starting with prefix
+    pub fn range(&self, prefix: &str) -> Option<&HashSet<String>> {
+        self.ranges.get(prefix)
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+        self.range(prefix)
+            .map(|suffixes| suffixes.contains(suffix))
+            .unwrap_or(false)
+    }
+}
+
+/// How hard a password is to guess, from 0 (trivially) to 4 (very hard),
+/// with the thresholds zxcvbn uses on the estimated number of guesses.
+/// Patterns an attacker would try first, such as the user's own name, common
+/// passwords, repeats and sequences, count as a single character each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    pub score: u8,
+    pub feedback: Vec<&'static str>,
+}
+
+pub fn strength(password: &str, user_inputs: &[&str]) -> Strength {
+    let mut feedback = Vec::new();
+    let mut remaining: Vec<char> = password.to_lowercase().chars().collect();
+
+    let mut personal = false;
+    for input in user_inputs {
+        for part in input
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| part.chars().count() >= 3)
+        {
+            personal |= replace_word(&mut remaining, part);
+        }
+    }
+    if personal {
+        feedback.push("avoid using your name or email address");
+    }
+
+    let mut common = false;
+    for word in COMMON_WORDS {
+        common |= replace_word(&mut remaining, word);
+    }
+    if common {
+        feedback.push("avoid common passwords and words");
+    }
+
+    let (collapsed, repeated, sequential) = collapse_patterns(&remaining);
+    if repeated {
+        feedback.push("avoid repeated characters like aaa");
+    }
+    if sequential {
+        feedback.push("avoid sequences like abc, 123 or qwerty");
+    }
+
+    let charset = charset_size(password) as f64;
+    let log10_guesses = collapsed as f64 * charset.log10();
+    let score = match log10_guesses {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    };
+    if score < 3 && feedback.is_empty() {
+        feedback.push("add another word or two; uncommon words are better");
+    }
+    Strength { score, feedback }
+}
+
+// Undoes the substitutions people make to dress up a common word
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    }
+}
+
+// Stands in for a word that was found, so it counts as one character
+const PLACEHOLDER: char = '\u{0}';
+
+// Replaces each occurrence of word, compared after unleet, with a placeholder
+fn replace_word(chars: &mut Vec<char>, word: &str) -> bool {
+    let word: Vec<char> = word.chars().map(unleet).collect();
+    let mut found = false;
+    let mut start = 0;
+    while start + word.len() <= chars.len() {
+        let matches = chars[start..start + word.len()]
+            .iter()
+            .zip(&word)
+            .all(|(&c, &w)| unleet(c) == w);
+        if matches {
+            chars.splice(start..start + word.len(), [PLACEHOLDER]);
+            found = true;
+        }
+        start += 1;
+    }
+    found
+}
+
+// Counts the characters left once every run of three or more repeated or
+// sequential characters is shrunk to one, reporting which kinds were found
+fn collapse_patterns(chars: &[char]) -> (usize, bool, bool) {
+    let mut collapsed = 0;
+    let (mut repeated, mut sequential) = (false, false);
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        while end < chars.len() && chars[end] == chars[start] {
+            end += 1;
+        }
+        if end - start >= 3 {
+            repeated = true;
+        } else {
+            end = start + 1;
+            let direction = chars.get(end).and_then(|&next| step(chars[start], next));
+            while end < chars.len()
+                && direction.is_some()
+                && step(chars[end - 1], chars[end]) == direction
+            {
+                end += 1;
+            }
+            if end - start >= 3 {
+                sequential = true;
+            } else {
+                end = start + 1;
+            }
+        }
+        collapsed += 1;
+        start = end;
+    }
+    (collapsed, repeated, sequential)
+}
+
+// Which way next follows on from previous, alphabetically or along a keyboard row
+fn step(previous: char, next: char) -> Option<i8> {
+    if previous.is_alphanumeric() && next.is_alphanumeric() {
+        match next as i64 - previous as i64 {
+            1 => return Some(1),
+            -1 => return Some(-1),
+            _ => (),
+        }
+    }
+    let forwards = format!("{}{}", previous, next);
+    let backwards = format!("{}{}", next, previous);
+    if KEYBOARD_ROWS.iter().any(|row| row.contains(&forwards)) {
+        Some(1)
+    } else if KEYBOARD_ROWS.iter().any(|row| row.contains(&backwards)) {
+        Some(-1)
+    } else {
+        None
+    }
+}
+
+fn charset_size(password: &str) -> u32 {
+    let mut size = 0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if password.chars().any(|c| !c.is_ascii_alphanumeric()) {
+        size += 33;
+    }
+    size.max(10)
+}
+
+/// The rules every new password has to meet, from the `password_policy` settings.
+#[derive(Debug)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub min_score: u8,
+    pub breached: BreachedPasswords,
+}
+
+impl PasswordPolicy {
+    /// Why a password is rejected, as a message to show next to the field, or
+    /// nothing when it passes. Only the most basic problem is reported.
+    /// user_inputs are things the password shouldn't be built from, like the user's name.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Vec<String> {
+        if password.chars().count() < self.min_length {
+            return vec![format!("must be at least {} characters", self.min_length)];
+        }
+        if self.breached.contains(password) {
+            return vec![
+                "has appeared in a data breach, so attackers will try it; choose another"
+                    .to_string(),
+            ];
+        }
+        let strength = strength(password, user_inputs);
+        if strength.score < self.min_score {
+            return vec![format!(
+                "is too easy to guess: {}",
+                strength.feedback.join(", ")
+            )];
+        }
+        vec![]
+    }
+}
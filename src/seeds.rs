@@ -0,0 +1,158 @@
+use anyhow::Context;
+use sqlx::{Executor, PgPool};
+use std::path::Path;
+
+/// A seed file, found at `seeds/<set>/<version>_<name>.sql`.
+/// Sets are applied in the order the environment lists them, files by version.
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub set: String,
+    pub version: String,
+    pub name: String,
+    pub sql: String,
+}
+
+impl Seed {
+    // The key recorded in seed_runs, e.g. "catalogue/20230221000000_skill_catalogue"
+    pub fn key(&self) -> String {
+        format!("{}/{}_{}", self.set, self.version, self.name)
+    }
+
+    // --only accepts the bare name, the file name without .sql, or the full key
+    fn matches(&self, only: &str) -> bool {
+        only == self.name || only == format!("{}_{}", self.version, self.name) || only == self.key()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SeedOptions {
+    pub dry_run: bool,
+    pub only: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SeedOutcome {
+    Applied,
+    // The file changed since it was last applied, so it ran again
+    Reapplied,
+    UpToDate,
+    // Dry runs execute the seed and roll it back
+    WouldApply,
+}
+
+#[derive(Debug)]
+pub struct SeedReport {
+    pub key: String,
+    pub outcome: SeedOutcome,
+}
+
+/// Reads the seed files for each set from `seeds_dir`, in the order they will run.
+pub fn load_seeds(seeds_dir: &Path, sets: &[String]) -> Result<Vec<Seed>, anyhow::Error> {
+    let mut seeds = Vec::new();
+    for set in sets {
+        let set_dir = seeds_dir.join(set);
+        let mut set_seeds = Vec::new();
+        let entries = std::fs::read_dir(&set_dir)
+            .with_context(|| format!("Unable to read seed set {}", set_dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("sql") {
+                continue;
+            }
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .with_context(|| format!("Seed file {} has no name", path.display()))?;
+            let (version, name) = stem.split_once('_').with_context(|| {
+                format!(
+                    "Seed file {} should be named <version>_<name>.sql",
+                    path.display()
+                )
+            })?;
+            let sql = std::fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read seed file {}", path.display()))?;
+            set_seeds.push(Seed {
+                set: set.clone(),
+                version: version.to_string(),
+                name: name.to_string(),
+                sql,
+            });
+        }
+        set_seeds.sort_by(|a, b| a.version.cmp(&b.version).then(a.name.cmp(&b.name)));
+        seeds.extend(set_seeds);
+    }
+    Ok(seeds)
+}
+
+/// Applies every seed that hasn't been applied yet or has changed since,
+/// each in its own transaction along with its seed_runs record.
+#[tracing::instrument(name = "Running seeds", skip(pool, seeds))]
+pub async fn run_seeds(
+    pool: &PgPool,
+    seeds: Vec<Seed>,
+    options: &SeedOptions,
+) -> Result<Vec<SeedReport>, anyhow::Error> {
+    let seeds: Vec<Seed> = match &options.only {
+        Some(only) => {
+            let selected: Vec<Seed> = seeds
+                .into_iter()
+                .filter(|seed| seed.matches(only))
+                .collect();
+            if selected.is_empty() {
+                anyhow::bail!("No seed named {}", only);
+            }
+            selected
+        }
+        None => seeds,
+    };
+
+    let mut reports = Vec::new();
+    for seed in seeds {
+        let key = seed.key();
+        let mut transaction = pool.begin().await?;
+        let previous_run: Option<(bool,)> =
+            sqlx::query_as("SELECT checksum = md5($2) FROM seed_runs WHERE name = $1")
+                .bind(&key)
+                .bind(&seed.sql)
+                .fetch_optional(&mut transaction)
+                .await
+                .context("Failed to read seed_runs")?;
+        if let Some((true,)) = previous_run {
+            reports.push(SeedReport {
+                key,
+                outcome: SeedOutcome::UpToDate,
+            });
+            continue;
+        }
+
+        // Executed as a plain query so a file can hold more than one statement
+        transaction
+            .execute(seed.sql.as_str())
+            .await
+            .with_context(|| format!("Failed to apply seed {}", key))?;
+        sqlx::query(
+            r#"
+    INSERT INTO seed_runs (name, checksum, applied_at) VALUES ($1, md5($2), now())
+    ON CONFLICT (name) DO UPDATE SET checksum = EXCLUDED.checksum, applied_at = EXCLUDED.applied_at
+    "#,
+        )
+        .bind(&key)
+        .bind(&seed.sql)
+        .execute(&mut transaction)
+        .await
+        .context("Failed to record seed run")?;
+
+        let outcome = if options.dry_run {
+            transaction.rollback().await?;
+            SeedOutcome::WouldApply
+        } else {
+            transaction.commit().await?;
+            match previous_run {
+                Some(_) => SeedOutcome::Reapplied,
+                None => SeedOutcome::Applied,
+            }
+        };
+        reports.push(SeedReport { key, outcome });
+    }
+    Ok(reports)
+}
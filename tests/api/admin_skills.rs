@@ -0,0 +1,276 @@
+use crate::helpers::{build_api_client, create_entry, rated_skills, spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::{DiaryForm, SkillCategoryForm, SkillForm};
+use shooting_star::models::{DiaryEntrySkills, Skill};
+
+fn skill_form(name: &str, category: &str, description: &str) -> SkillForm {
+    SkillForm {
+        name: name.to_string(),
+        category: category.to_string(),
+        description: description.to_string(),
+    }
+}
+
+async fn create_global_skill(app: &TestApp, name: &str) -> Skill {
+    let response = app
+        .api_client
+        .post(format!("{}/admin/skills", &app.address))
+        .json(&skill_form(name, "mindfulness", ""))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn list_skills(app: &TestApp, client: &reqwest::Client) -> Vec<Skill> {
+    let response = client
+        .get(format!("{}/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn admin_endpoints_reject_anonymous_and_non_admin_users() {
+    let app = spawn_app().await;
+    let body = skill_form("observe", "mindfulness", "");
+
+    let response = app
+        .api_client
+        .post(format!("{}/admin/skills", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+
+    app.login_test_user().await;
+    let response = app
+        .api_client
+        .post(format!("{}/admin/skills", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+    let response = app
+        .api_client
+        .delete(format!("{}/admin/skills/1", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn admins_can_create_update_and_recategorize_global_skills() {
+    let app = spawn_app().await;
+    app.login_admin_user().await;
+
+    let skill = create_global_skill(&app, "observe").await;
+    assert_eq!(skill.user_id, None);
+
+    let response = app
+        .api_client
+        .patch(format!("{}/admin/skills/{}", &app.address, skill.id))
+        .json(&skill_form(
+            "observe",
+            "mindfulness",
+            "Notice without reacting",
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .put(format!(
+            "{}/admin/skills/{}/category",
+            &app.address, skill.id
+        ))
+        .json(&SkillCategoryForm {
+            category: "distress_tolerance".to_string(),
+        })
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    // Every user sees the maintained skill
+    let user = build_api_client();
+    app.login_test_user_with(&user).await;
+    let skills = list_skills(&app, &user).await;
+    assert_eq!(skills.len(), 1);
+    assert_eq!(skills[0].description, "Notice without reacting");
+    assert_eq!(skills[0].category, "distress_tolerance");
+
+    let response = app
+        .api_client
+        .patch(format!("{}/admin/skills/{}", &app.address, skill.id))
+        .json(&skill_form("", "mindfulness", ""))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .post(format!("{}/admin/skills", &app.address))
+        .json(&skill_form("observe", "distress_tolerance", ""))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn soft_deleted_skills_leave_the_catalogue() {
+    let app = spawn_app().await;
+    app.login_admin_user().await;
+    let retired = create_global_skill(&app, "observe").await;
+    create_global_skill(&app, "describe").await;
+
+    let response = app
+        .api_client
+        .delete(format!("{}/admin/skills/{}", &app.address, retired.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+
+    let names: Vec<String> = list_skills(&app, &app.api_client)
+        .await
+        .into_iter()
+        .map(|skill| skill.name)
+        .collect();
+    assert_eq!(names, vec!["describe"]);
+
+    for response in [
+        app.api_client
+            .delete(format!("{}/admin/skills/{}", &app.address, retired.id))
+            .send(),
+        app.api_client
+            .patch(format!("{}/admin/skills/{}", &app.address, retired.id))
+            .json(&skill_form("observe", "mindfulness", ""))
+            .send(),
+        app.api_client
+            .get(format!("{}/skills/{}", &app.address, retired.id))
+            .send(),
+    ] {
+        let response = response.await.expect("Failed to execute request.");
+        assert_eq!(404, response.status().as_u16());
+    }
+}
+
+// The (skill id, rating) pairs logged on the user's entry for date
+async fn entry_ratings(app: &TestApp, date: &str) -> Vec<(i32, Option<i32>)> {
+    let skills: Vec<DiaryEntrySkills> = app
+        .api_client
+        .get(format!("{}/diary_entries/{}/skills", &app.address, date))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let mut ratings: Vec<(i32, Option<i32>)> = skills
+        .iter()
+        .map(|skill| (skill.skills_id, skill.rating))
+        .collect();
+    ratings.sort();
+    ratings
+}
+
+#[actix_rt::test]
+async fn entries_keep_retired_skills_when_saved_again() {
+    let app = spawn_app().await;
+    app.login_admin_user().await;
+    let retired = create_global_skill(&app, "observe").await;
+    let kept = create_global_skill(&app, "describe").await;
+    let entry = create_entry(&app, "2022-02-07", &rated_skills(&[retired.id, kept.id], 4)).await;
+    let response = app
+        .api_client
+        .delete(format!("{}/admin/skills/{}", &app.address, retired.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let body = DiaryForm {
+        entry_date: DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc),
+        skills: rated_skills(&[retired.id, kept.id], 5),
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = app
+        .api_client
+        .patch(format!("{}/diary_entries/{}", &app.address, entry.id))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        entry_ratings(&app, "2022-02-07").await,
+        vec![(retired.id, Some(5)), (kept.id, Some(5))]
+    );
+
+    let body = DiaryForm {
+        skills: rated_skills(&[retired.id, kept.id], 6),
+        ..body
+    };
+    let response = app
+        .api_client
+        .put(format!("{}/diary_entries/2022-02-07", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        entry_ratings(&app, "2022-02-07").await,
+        vec![(retired.id, Some(6)), (kept.id, Some(6))]
+    );
+
+    // Entries that didn't already use the retired skill can't pick it up
+    create_entry(&app, "2022-02-08", &rated_skills(&[retired.id, kept.id], 4)).await;
+    assert_eq!(
+        entry_ratings(&app, "2022-02-08").await,
+        vec![(kept.id, Some(4))]
+    );
+}
+
+#[actix_rt::test]
+async fn admins_cannot_edit_private_skills_through_the_catalogue() {
+    let app = spawn_app().await;
+    let owner = build_api_client();
+    app.login_test_user_with(&owner).await;
+    let response = owner
+        .post(format!("{}/skills", &app.address))
+        .json(&skill_form("wise mind walk", "mindfulness", ""))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let private_skill: Skill = response.json().await.unwrap();
+
+    app.login_admin_user().await;
+    let response = app
+        .api_client
+        .put(format!(
+            "{}/admin/skills/{}/category",
+            &app.address, private_skill.id
+        ))
+        .json(&SkillCategoryForm {
+            category: "distress_tolerance".to_string(),
+        })
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
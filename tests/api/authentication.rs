@@ -1,21 +1,63 @@
-use crate::helpers::spawn_app;
-use shooting_star::configuration::get_configuration;
-use uuid::Uuid;
+use crate::helpers::{rated_skills, spawn_app};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::DiaryForm;
 
 #[actix_rt::test]
 async fn non_exisiting_user_is_rejected() {
     let app = spawn_app().await;
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let username = Uuid::new_v4().to_string();
-    let password = Uuid::new_v4().to_string();
 
-    let client = reqwest::Client::new();
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: rated_skills(&[1, 3, 5], 4),
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
 
-    let response = client
-        .post(&format!("{}/diary_entries", &app.address))
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
         .json(&body)
         .send()
         .await
         .expect("Failed to execute request.");
     assert_eq!(401, response.status().as_u16());
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].is_string());
+}
+
+#[actix_rt::test]
+async fn anonymous_diary_entry_reads_are_rejected() {
+    let app = spawn_app().await;
+
+    for path in [
+        "/diary_entries",
+        "/diary_entries/2022-02-07",
+        "/diary_entries/2022-02-07/skills",
+    ] {
+        let response = app
+            .api_client
+            .get(format!("{}{}", &app.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(401, response.status().as_u16(), "GET {}", path);
+    }
+}
+
+#[actix_rt::test]
+async fn logged_in_user_is_accepted() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
 }
@@ -1,52 +1,32 @@
-use crate::helpers::{create_test_user, spawn_app};
+use crate::helpers::{create_test_data, rated_skills, spawn_app};
 use chrono::{DateTime, NaiveDate, Utc};
-use shooting_star::configuration::get_configuration;
 use shooting_star::controllers::DiaryForm;
 use shooting_star::models::DiaryEntry;
-use sqlx::Executor;
 use sqlx::{postgres::PgConnection, Connection};
 
-async fn create_test_data(connection: PgConnection) {
-    let query = r#"INSERT INTO skills (name, category)
-       VALUES
-       ('observe', 'mindfulness'),
-       ('describe', 'mindfulness'),
-       ('activities', 'distress_tolerance'),
-       ('contributing', 'distress_tolerance'),
-       ('sleep', 'emotion_regulation'),
-       ('eating', 'emotion_regulation')"#;
-    let mut pg_connection = connection;
-    pg_connection
-        .execute(query)
-        .await
-        .expect("Unable to add skills to database");
-}
-
 #[actix_rt::test]
 async fn create_diary_entry_returns_a_201_for_valid_form_data() {
     let app = spawn_app().await;
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let data_connection = PgConnection::connect(&configuration.database.connection_string())
+    let data_connection = PgConnection::connect(&app.db_url)
         .await
         .expect("Failed to connect to Postgres");
     create_test_data(data_connection).await;
-    let user_connection = PgConnection::connect(&configuration.database.connection_string())
-        .await
-        .expect("Failed to connect to Postgres");
-    create_test_user(user_connection).await;
-
-    let client = reqwest::Client::new();
+    app.login_test_user().await;
+    let client = &app.api_client;
 
     let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
     let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
     let ids: Vec<i32> = vec![1, 3, 5];
     let body = DiaryForm {
-        entry_date: datetime_utc.into(),
-        skill_ids: ids.into(),
+        entry_date: datetime_utc,
+        skills: rated_skills(&ids, 4),
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
     };
 
     let response = client
-        .post(&format!("{}/diary_entries", &app.address))
+        .post(format!("{}/diary_entries", &app.address))
         .json(&body)
         .send()
         .await
@@ -62,22 +42,25 @@ async fn create_diary_entry_returns_a_201_for_valid_form_data() {
 async fn create_diary_entry_adds_diary_entry_skills() {
     let app = spawn_app().await;
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let connection = PgConnection::connect(&configuration.database.connection_string())
+    let connection = PgConnection::connect(&app.db_url)
         .await
         .expect("Failed to connect to Postgres");
     create_test_data(connection).await;
-    let client = reqwest::Client::new();
+    app.login_test_user().await;
+    let client = &app.api_client;
     let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
     let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
     let ids: Vec<i32> = vec![1, 3, 5];
     let body = DiaryForm {
-        entry_date: datetime_utc.into(),
-        skill_ids: ids.into(),
+        entry_date: datetime_utc,
+        skills: rated_skills(&ids, 4),
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
     };
 
     let response = client
-        .post(&format!("{}/diary_entries", &app.address))
+        .post(format!("{}/diary_entries", &app.address))
         .json(&body)
         .send()
         .await
@@ -89,7 +72,7 @@ async fn create_diary_entry_adds_diary_entry_skills() {
     assert_eq!(&entry_date, "2022-02-07");
 
     let diary_entries_skills = client
-        .get(&format!(
+        .get(format!(
             "{}/diary_entries/{}/skills",
             &app.address, &entry_date
         ))
@@ -0,0 +1,186 @@
+use crate::helpers::{build_api_client, create_test_data, spawn_app, TestApp};
+use shooting_star::controllers::{SkillForm, SkillOrderForm};
+use shooting_star::models::Skill;
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn skill_names(app: &TestApp, client: &reqwest::Client) -> Vec<String> {
+    let response = client
+        .get(format!("{}/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let skills: Vec<Skill> = response.json().await.unwrap();
+    skills.into_iter().map(|skill| skill.name).collect()
+}
+
+async fn create_private_skill(app: &TestApp, name: &str) -> Skill {
+    let body = SkillForm {
+        name: name.to_string(),
+        category: "mindfulness".to_string(),
+        description: "".to_string(),
+    };
+    let response = app
+        .api_client
+        .post(format!("{}/skills", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn private_skills_are_only_listed_for_their_owner() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let skill = create_private_skill(&app, "wise mind walk").await;
+    assert!(skill.user_id.is_some());
+    assert_eq!(
+        skill_names(&app, &app.api_client).await,
+        vec![
+            "observe",
+            "describe",
+            "activities",
+            "contributing",
+            "sleep",
+            "eating",
+            "wise mind walk"
+        ]
+    );
+
+    let other_user = build_api_client();
+    app.login_test_user_with(&other_user).await;
+    assert_eq!(
+        skill_names(&app, &other_user).await,
+        vec![
+            "observe",
+            "describe",
+            "activities",
+            "contributing",
+            "sleep",
+            "eating"
+        ]
+    );
+    let response = other_user
+        .get(format!("{}/skills/{}", &app.address, skill.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+    let response = other_user
+        .put(format!("{}/skills/{}/hidden", &app.address, skill.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn hidden_skills_are_left_off_the_card_until_unhidden() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let response = app
+        .api_client
+        .put(format!("{}/skills/2/hidden", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(
+        skill_names(&app, &app.api_client).await,
+        vec!["observe", "activities", "contributing", "sleep", "eating"]
+    );
+
+    // Hiding is per user
+    let other_user = build_api_client();
+    app.login_test_user_with(&other_user).await;
+    assert_eq!(
+        skill_names(&app, &other_user).await,
+        vec![
+            "observe",
+            "describe",
+            "activities",
+            "contributing",
+            "sleep",
+            "eating"
+        ]
+    );
+
+    let response = app
+        .api_client
+        .delete(format!("{}/skills/2/hidden", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(
+        skill_names(&app, &app.api_client).await,
+        vec![
+            "observe",
+            "describe",
+            "activities",
+            "contributing",
+            "sleep",
+            "eating"
+        ]
+    );
+}
+
+#[actix_rt::test]
+async fn skills_are_listed_in_the_users_order() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    let skill = create_private_skill(&app, "wise mind walk").await;
+
+    let body = SkillOrderForm {
+        skill_ids: vec![skill.id, 3],
+    };
+    let response = app
+        .api_client
+        .put(format!("{}/skills/order", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(
+        skill_names(&app, &app.api_client).await,
+        vec![
+            "wise mind walk",
+            "activities",
+            "observe",
+            "describe",
+            "contributing",
+            "sleep",
+            "eating"
+        ]
+    );
+
+    for skill_ids in [vec![1, 1], vec![1, 99]] {
+        let body = SkillOrderForm { skill_ids };
+        let response = app
+            .api_client
+            .put(format!("{}/skills/order", &app.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(400, response.status().as_u16());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::helpers::{build_api_client, create_entry, create_test_data, rated_skills, spawn_app};
+use shooting_star::models::{DiaryEntry, DiaryEntrySkills};
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn status(request: reqwest::RequestBuilder) -> u16 {
+    request
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+#[actix_rt::test]
+async fn deleted_entries_are_hidden_until_restored() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    let entry = create_entry(&app, "2022-02-07", &rated_skills(&[1, 2], 4)).await;
+    let entry_url = format!("{}/diary_entries/{}", &app.address, entry.id);
+    let show_url = format!("{}/diary_entries/2022-02-07", &app.address);
+
+    assert_eq!(204, status(app.api_client.delete(&entry_url)).await);
+    assert_eq!(404, status(app.api_client.get(&show_url)).await);
+    assert_eq!(404, status(app.api_client.delete(&entry_url)).await);
+    let skills: Vec<DiaryEntrySkills> = app
+        .api_client
+        .get(format!("{}/skills", &show_url))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert!(skills.is_empty());
+
+    let response = app
+        .api_client
+        .post(format!("{}/restore", &entry_url))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let restored: DiaryEntry = response.json().await.unwrap();
+    assert_eq!(restored, entry);
+    assert_eq!(200, status(app.api_client.get(&show_url)).await);
+    let skills: Vec<DiaryEntrySkills> = app
+        .api_client
+        .get(format!("{}/skills", &show_url))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(skills.len(), 2);
+
+    // Only deleted entries can be restored
+    assert_eq!(
+        404,
+        status(app.api_client.post(format!("{}/restore", &entry_url))).await
+    );
+}
+
+#[actix_rt::test]
+async fn other_users_cannot_delete_or_restore_an_entry() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+    let entry = create_entry(&app, "2022-02-07", &rated_skills(&[1, 2], 4)).await;
+    let entry_url = format!("{}/diary_entries/{}", &app.address, entry.id);
+
+    let intruder = build_api_client();
+    app.login_test_user_with(&intruder).await;
+    assert_eq!(404, status(intruder.delete(&entry_url)).await);
+
+    assert_eq!(204, status(app.api_client.delete(&entry_url)).await);
+    assert_eq!(
+        404,
+        status(intruder.post(format!("{}/restore", &entry_url))).await
+    );
+    assert_eq!(
+        400,
+        status(
+            app.api_client
+                .delete(format!("{}/diary_entries/abc", &app.address))
+        )
+        .await
+    );
+}
+
+#[actix_rt::test]
+async fn entries_past_the_restore_window_are_purged() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    let expired = create_entry(&app, "2022-02-07", &rated_skills(&[1, 2], 4)).await;
+    let recent = create_entry(&app, "2022-02-08", &rated_skills(&[1, 2], 4)).await;
+
+    let mut connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    sqlx::query("UPDATE diary_entries SET deleted_at = now() - interval '31 days' WHERE id = $1")
+        .bind(expired.id)
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(
+        404,
+        status(app.api_client.post(format!(
+            "{}/diary_entries/{}/restore",
+            &app.address, expired.id
+        )))
+        .await
+    );
+
+    assert_eq!(
+        204,
+        status(
+            app.api_client
+                .delete(format!("{}/diary_entries/{}", &app.address, recent.id))
+        )
+        .await
+    );
+    let (entries, links): (i64, i64) = sqlx::query_as(
+        "SELECT (SELECT count(*) FROM diary_entries WHERE id = $1),
+                (SELECT count(*) FROM diary_entries_skills WHERE diary_entry_id = $1)",
+    )
+    .bind(expired.id)
+    .fetch_one(&mut connection)
+    .await
+    .unwrap();
+    assert_eq!((entries, links), (0, 0));
+}
@@ -0,0 +1,128 @@
+use crate::helpers::{create_entry, create_test_data, rated_skills, spawn_app, TestApp};
+use shooting_star::controllers::SettingsForm;
+use shooting_star::models::{DiaryCard, UserSettings};
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn get_card(app: &TestApp, iso_week: &str) -> (u16, Option<DiaryCard>) {
+    let response = app
+        .api_client
+        .get(format!("{}/diary_cards/{}", &app.address, iso_week))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status().as_u16();
+    if status != 200 {
+        return (status, None);
+    }
+    (status, Some(response.json().await.unwrap()))
+}
+
+async fn set_week_start(app: &TestApp, week_start: &str) -> u16 {
+    let body = SettingsForm {
+        week_start: Some(week_start.to_string()),
+        ..Default::default()
+    };
+    app.api_client
+        .patch(format!("{}/account/settings", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+fn first_day(card: &DiaryCard) -> String {
+    card.days[0].date.to_string()
+}
+
+#[actix_rt::test]
+async fn a_week_is_shown_as_skills_by_days() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    let monday = create_entry(&app, "2022-02-07", &rated_skills(&[1], 3)).await;
+    let wednesday = create_entry(&app, "2022-02-09", &rated_skills(&[1, 3], 3)).await;
+    create_entry(&app, "2022-02-14", &rated_skills(&[3], 3)).await;
+
+    let (_, card) = get_card(&app, "2022-W06").await;
+    let card = card.unwrap();
+    assert_eq!(card.week, "2022-W06");
+    assert_eq!(first_day(&card), "2022-02-07");
+    let entry_ids: Vec<Option<i32>> = card.days.iter().map(|day| day.diary_entry_id).collect();
+    assert_eq!(
+        entry_ids,
+        vec![
+            Some(monday.id),
+            None,
+            Some(wednesday.id),
+            None,
+            None,
+            None,
+            None
+        ]
+    );
+    let grid: Vec<(&str, Vec<Option<i32>>)> = card
+        .rows
+        .iter()
+        .map(|row| {
+            (
+                row.name.as_str(),
+                row.cells
+                    .iter()
+                    .map(|cell| cell.as_ref().and_then(|cell| cell.rating))
+                    .collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        grid,
+        vec![
+            (
+                "observe",
+                vec![Some(3), None, Some(3), None, None, None, None]
+            ),
+            ("describe", vec![None; 7]),
+            (
+                "activities",
+                vec![None, None, Some(3), None, None, None, None]
+            ),
+            ("contributing", vec![None; 7]),
+            ("sleep", vec![None; 7]),
+            ("eating", vec![None; 7]),
+        ]
+    );
+
+    for iso_week in ["2022-06", "2022-W54", "22-W06"] {
+        assert_eq!(get_card(&app, iso_week).await.0, 400, "{}", iso_week);
+    }
+}
+
+#[actix_rt::test]
+async fn cards_start_on_the_users_week_start_day() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    assert_eq!(200, set_week_start(&app, "Sunday").await);
+    let settings: UserSettings = app
+        .api_client
+        .get(format!("{}/account/settings", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(settings.week_start, "sunday");
+    let (_, card) = get_card(&app, "2022-W06").await;
+    assert_eq!(first_day(&card.unwrap()), "2022-02-06");
+
+    assert_eq!(200, set_week_start(&app, "tuesday").await);
+    let (_, card) = get_card(&app, "2022-W06").await;
+    assert_eq!(first_day(&card.unwrap()), "2022-02-08");
+
+    assert_eq!(400, set_week_start(&app, "someday").await);
+}
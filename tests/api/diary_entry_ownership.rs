@@ -0,0 +1,136 @@
+use crate::helpers::{build_api_client, create_test_data, rated_skills, spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::{DiaryForm, Page};
+use shooting_star::models::{DiaryEntry, DiaryEntrySkills};
+use sqlx::{postgres::PgConnection, Connection};
+
+// Creates a diary entry for 2022-02-07 with three skills as the api_client's user
+async fn create_owned_entry(app: &TestApp) -> DiaryEntry {
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: rated_skills(&[1, 3, 5], 4),
+        notes: "owner's notes".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn updating_another_users_diary_entry_returns_a_404() {
+    let app = spawn_app().await;
+    let entry = create_owned_entry(&app).await;
+
+    let intruder = build_api_client();
+    app.login_test_user_with(&intruder).await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: rated_skills(&[2], 4),
+        notes: "intruder's notes".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = intruder
+        .patch(format!("{}/diary_entries/{}", &app.address, entry.id))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+
+    // The owner's entry and skill links are untouched
+    let show_response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, show_response.status().as_u16());
+    let shown: DiaryEntry = show_response.json().await.unwrap();
+    assert_eq!(shown.notes, "owner's notes");
+
+    let skills_response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, skills_response.status().as_u16());
+    let skills: Vec<DiaryEntrySkills> = skills_response.json().await.unwrap();
+    assert_eq!(skills.len(), 3);
+}
+
+#[actix_rt::test]
+async fn another_users_diary_entry_is_not_visible() {
+    let app = spawn_app().await;
+    create_owned_entry(&app).await;
+
+    let intruder = build_api_client();
+    app.login_test_user_with(&intruder).await;
+
+    let show_response = intruder
+        .get(format!("{}/diary_entries/2022-02-07", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, show_response.status().as_u16());
+
+    let skills_response = intruder
+        .get(format!("{}/diary_entries/2022-02-07/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let skills: Vec<DiaryEntrySkills> = skills_response.json().await.unwrap();
+    assert!(skills.is_empty());
+
+    let index_response = intruder
+        .get(format!("{}/diary_entries", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let entries: Page<DiaryEntry> = index_response.json().await.unwrap();
+    assert!(entries.items.is_empty());
+}
+
+#[actix_rt::test]
+async fn updating_a_non_numeric_diary_entry_id_returns_a_400() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: vec![],
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = app
+        .api_client
+        .patch(format!("{}/diary_entries/not-an-id", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,116 @@
+use crate::helpers::{create_test_data, rated_skills, spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::DiaryForm;
+use shooting_star::models::{DiaryEntry, DiaryEntrySkills};
+use sqlx::{postgres::PgConnection, Connection};
+
+fn diary_form(date: &str, skill_ids: &[i32], notes: &str) -> DiaryForm {
+    let naive_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    DiaryForm {
+        entry_date: datetime_utc,
+        skills: rated_skills(skill_ids, 4),
+        notes: notes.to_string(),
+        emotions: vec![],
+        urges: vec![],
+    }
+}
+
+async fn post_entry(app: &TestApp, body: &DiaryForm) -> reqwest::Response {
+    app.api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn put_entry(app: &TestApp, date: &str, body: &DiaryForm) -> reqwest::Response {
+    app.api_client
+        .put(format!("{}/diary_entries/{}", &app.address, date))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn a_second_entry_for_the_same_day_is_a_conflict() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+    let body = diary_form("2022-02-07", &[], "first");
+
+    assert_eq!(201, post_entry(&app, &body).await.status().as_u16());
+    let response = post_entry(&app, &body).await;
+    assert_eq!(409, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        error["error"],
+        "There is already a diary entry for that date"
+    );
+}
+
+#[actix_rt::test]
+async fn put_creates_then_replaces_the_days_entry() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let response = put_entry(&app, "2022-02-07", &diary_form("2022-02-07", &[1], "draft")).await;
+    assert_eq!(201, response.status().as_u16());
+    let created: DiaryEntry = response.json().await.unwrap();
+
+    let body = diary_form("2022-02-07", &[2], "final");
+    for _ in 0..2 {
+        let response = put_entry(&app, "2022-02-07", &body).await;
+        assert_eq!(200, response.status().as_u16());
+        let replaced: DiaryEntry = response.json().await.unwrap();
+        assert_eq!(replaced.id, created.id);
+        assert_eq!(replaced.notes, "final");
+    }
+    let skills: Vec<DiaryEntrySkills> = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let skill_ids: Vec<i32> = skills.iter().map(|skill| skill.skills_id).collect();
+    assert_eq!(skill_ids, vec![2]);
+
+    let response = put_entry(&app, "2022-02-08", &body).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn restoring_over_a_newer_entry_for_the_day_is_a_conflict() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+    let body = diary_form("2022-02-07", &[], "");
+    let deleted: DiaryEntry = post_entry(&app, &body).await.json().await.unwrap();
+    let response = app
+        .api_client
+        .delete(format!("{}/diary_entries/{}", &app.address, deleted.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(204, response.status().as_u16());
+
+    // The day can be written again while the deleted entry awaits purging
+    assert_eq!(201, post_entry(&app, &body).await.status().as_u16());
+    let response = app
+        .api_client
+        .post(format!(
+            "{}/diary_entries/{}/restore",
+            &app.address, deleted.id
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+}
@@ -0,0 +1,160 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+use shooting_star::configuration::get_configuration;
+use shooting_star::controllers::credentials_controller::WhoAmI;
+use shooting_star::models::EmailVerification;
+use sqlx::{postgres::PgConnection, Connection};
+
+// Signs up and logs in a new user, returning them with their verification token
+async fn signup(app: &TestApp) -> (TestUser, String) {
+    let user = TestUser::generate();
+    let response = app
+        .api_client
+        .post(format!("{}/signup", &app.address))
+        .json(&user)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let response = app.post_login(&user).await;
+    assert_eq!(200, response.status().as_u16());
+    let token = last_token(app, &user.email);
+    (user, token)
+}
+
+fn last_token(app: &TestApp, to: &str) -> String {
+    let sent = app.mailer.sent();
+    let email = sent.last().expect("No email was sent");
+    assert_eq!(email.to, to);
+    let (_, rest) = email.body.split_once("token=").unwrap();
+    rest.split_whitespace().next().unwrap().to_string()
+}
+
+async fn verify(app: &TestApp, token: &str) -> u16 {
+    app.api_client
+        .get(format!("{}/verify_email", &app.address))
+        .query(&[("token", token)])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+async fn get_status(app: &TestApp, path: &str) -> u16 {
+    app.api_client
+        .get(format!("{}{}", &app.address, path))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+async fn email_verified(app: &TestApp) -> bool {
+    let response = app
+        .api_client
+        .get(format!("{}/whoami", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let whoami: WhoAmI = response.json().await.unwrap();
+    whoami.email_verified
+}
+
+#[actix_rt::test]
+async fn new_accounts_are_limited_until_their_email_is_verified() {
+    let app = spawn_app().await;
+    let (_, token) = signup(&app).await;
+
+    assert!(!email_verified(&app).await);
+    for path in ["/diary_entries", "/skills", "/stats"] {
+        assert_eq!(403, get_status(&app, path).await, "GET {}", path);
+    }
+    assert_eq!(200, get_status(&app, "/account/settings").await);
+
+    assert_eq!(204, verify(&app, &token).await);
+    assert!(email_verified(&app).await);
+    for path in ["/diary_entries", "/skills", "/stats"] {
+        assert_eq!(200, get_status(&app, path).await, "GET {}", path);
+    }
+}
+
+#[actix_rt::test]
+async fn tampered_expired_and_stale_links_are_rejected() {
+    let app = spawn_app().await;
+    let (user, token) = signup(&app).await;
+    let (user_id, _) = token.split_once('.').unwrap();
+    let user_id: i32 = user_id.parse().unwrap();
+
+    let mut tampered = token.clone();
+    tampered.replace_range(token.len() - 1.., "x");
+    let other_user = token.replacen(&format!("{}.", user_id), &format!("{}.", user_id + 1), 1);
+    assert_eq!(400, verify(&app, &tampered).await);
+    assert_eq!(400, verify(&app, &other_user).await);
+    assert_eq!(400, verify(&app, "not-a-token").await);
+
+    let secret = get_configuration().unwrap().hmac_secret;
+    let expired = EmailVerification {
+        user_id,
+        expires_at: chrono::Utc::now().timestamp() - 60,
+    }
+    .token(&user.email, &secret);
+    assert_eq!(400, verify(&app, &expired).await);
+
+    // Links have a key of their own, so one signed with the session secret itself fails
+    let expires_at = chrono::Utc::now().timestamp() + 3600;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose_secret().as_bytes()).unwrap();
+    mac.update(format!("{}.{}.{}", user_id, expires_at, user.email).as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+    let session_signed = format!("{}.{}.{}", user_id, expires_at, signature);
+    assert_eq!(400, verify(&app, &session_signed).await);
+
+    // A link stops working once the address it was sent to changes
+    let mut connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    sqlx::query("UPDATE users SET email = 'changed@example.com' WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(400, verify(&app, &token).await);
+    assert!(!email_verified(&app).await);
+}
+
+#[actix_rt::test]
+async fn verification_links_can_be_resent_until_verified() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/verification", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+
+    let (user, _) = signup(&app).await;
+    let response = app
+        .api_client
+        .post(format!("{}/account/verification", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(202, response.status().as_u16());
+    assert_eq!(app.mailer.sent().len(), 2);
+
+    let token = last_token(&app, &user.email);
+    assert_eq!(204, verify(&app, &token).await);
+    let response = app
+        .api_client
+        .post(format!("{}/account/verification", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+}
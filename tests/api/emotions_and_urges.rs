@@ -0,0 +1,180 @@
+use crate::helpers::{spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::{DiaryForm, EmotionRating, UrgeRating};
+use shooting_star::models::{DiaryEntry, DiaryEntryEmotion, DiaryEntryUrge, Emotion, Urge};
+
+fn diary_form(emotions: Vec<EmotionRating>, urges: Vec<UrgeRating>) -> DiaryForm {
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    DiaryForm {
+        entry_date: datetime_utc,
+        skills: vec![],
+        notes: "".to_string(),
+        emotions,
+        urges,
+    }
+}
+
+// Returns the shown entry's id with its emotion and urge intensities
+async fn show_diary_entry(app: &TestApp) -> (i32, Vec<DiaryEntryEmotion>, Vec<DiaryEntryUrge>) {
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    (
+        body["id"].as_i64().unwrap() as i32,
+        serde_json::from_value(body["emotions"].clone()).unwrap(),
+        serde_json::from_value(body["urges"].clone()).unwrap(),
+    )
+}
+
+#[actix_rt::test]
+async fn emotion_and_urge_definitions_are_listed() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/emotions", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let emotions: Vec<Emotion> = response.json().await.unwrap();
+    let names: Vec<&str> = emotions
+        .iter()
+        .map(|emotion| emotion.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["sadness", "anger", "shame", "joy"]);
+
+    let response = app
+        .api_client
+        .get(format!("{}/urges", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let urges: Vec<Urge> = response.json().await.unwrap();
+    let names: Vec<&str> = urges.iter().map(|urge| urge.name.as_str()).collect();
+    assert_eq!(names, vec!["self_harm", "substance_use"]);
+}
+
+#[actix_rt::test]
+async fn intensities_are_saved_shown_and_replaced_on_update() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    let body = diary_form(
+        vec![
+            EmotionRating {
+                emotion_id: 1,
+                intensity: 0,
+            },
+            EmotionRating {
+                emotion_id: 4,
+                intensity: 5,
+            },
+        ],
+        vec![UrgeRating {
+            urge_id: 2,
+            intensity: 3,
+        }],
+    );
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    let entry: DiaryEntry = response.json().await.unwrap();
+
+    let (id, emotions, urges) = show_diary_entry(&app).await;
+    assert_eq!(id, entry.id);
+    let emotions: Vec<(&str, i32)> = emotions
+        .iter()
+        .map(|emotion| (emotion.name.as_str(), emotion.intensity))
+        .collect();
+    assert_eq!(emotions, vec![("sadness", 0), ("joy", 5)]);
+    let urges: Vec<(&str, i32)> = urges
+        .iter()
+        .map(|urge| (urge.name.as_str(), urge.intensity))
+        .collect();
+    assert_eq!(urges, vec![("substance_use", 3)]);
+
+    let mut body = diary_form(
+        vec![EmotionRating {
+            emotion_id: 2,
+            intensity: 2,
+        }],
+        vec![],
+    );
+    body.notes = "calmer by the evening".to_string();
+    let response = app
+        .api_client
+        .patch(format!("{}/diary_entries/{}", &app.address, entry.id))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let updated: DiaryEntry = response.json().await.unwrap();
+    assert_eq!(updated.id, entry.id);
+    assert_eq!(updated.notes, "calmer by the evening");
+
+    let (_, emotions, urges) = show_diary_entry(&app).await;
+    let emotions: Vec<(&str, i32)> = emotions
+        .iter()
+        .map(|emotion| (emotion.name.as_str(), emotion.intensity))
+        .collect();
+    assert_eq!(emotions, vec![("anger", 2)]);
+    assert!(urges.is_empty());
+}
+
+#[actix_rt::test]
+async fn intensities_outside_the_configured_scale_are_rejected() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    let out_of_scale = vec![
+        diary_form(
+            vec![EmotionRating {
+                emotion_id: 1,
+                intensity: 6,
+            }],
+            vec![],
+        ),
+        diary_form(
+            vec![],
+            vec![UrgeRating {
+                urge_id: 1,
+                intensity: -1,
+            }],
+        ),
+    ];
+    for body in out_of_scale {
+        let response = app
+            .api_client
+            .post(format!("{}/diary_entries", &app.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(400, response.status().as_u16());
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert!(error["error"].as_str().unwrap().contains("scale"));
+    }
+
+    // Nothing is written for a rejected form
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
@@ -1,18 +1,164 @@
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
 use serde::{Deserialize, Serialize};
-use shooting_star::configuration::{get_configuration, AppData};
-use shooting_star::controllers::LoginForm;
-use shooting_star::models::Credentials;
+use shooting_star::configuration::{get_configuration, AppData, DatabaseSettings};
+use shooting_star::controllers::{DiaryForm, SkillRating};
+use shooting_star::mailer::InMemoryMailer;
+use shooting_star::models::DiaryEntry;
 use shooting_star::run;
 use sqlx::postgres::PgConnection;
-use sqlx::Connection;
+use sqlx::{Connection, Executor, PgPool};
+use std::collections::HashMap;
 use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+// The session cookie is marked Secure, which reqwest's default jar refuses to
+// send over plain http. Tests talk to 127.0.0.1, so keep cookies regardless.
+#[derive(Default)]
+pub struct TestCookieJar(Mutex<HashMap<String, String>>);
+
+impl CookieStore for TestCookieJar {
+    fn set_cookies(
+        &self,
+        cookie_headers: &mut dyn Iterator<Item = &HeaderValue>,
+        _: &reqwest::Url,
+    ) {
+        let mut cookies = self.0.lock().unwrap();
+        for header in cookie_headers {
+            let pair = header
+                .to_str()
+                .ok()
+                .and_then(|h| h.split(';').next())
+                .and_then(|pair| pair.split_once('='));
+            if let Some((name, value)) = pair {
+                if value.is_empty() {
+                    cookies.remove(name);
+                } else {
+                    cookies.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+    }
+
+    fn cookies(&self, _: &reqwest::Url) -> Option<HeaderValue> {
+        let cookies = self.0.lock().unwrap();
+        if cookies.is_empty() {
+            return None;
+        }
+        let header = cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
 pub struct TestApp {
     pub address: String,
     pub db_url: String,
+    pub app_data: AppData,
+    pub api_client: reqwest::Client,
+    // Captures the emails the app sends
+    pub mailer: Arc<InMemoryMailer>,
+    db_name: String,
+    db_server_url: String,
+}
+
+// Each TestApp owns its database, so remove it once the test is done with it.
+// Drop can't be async, so run the cleanup on a dedicated runtime.
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let db_name = self.db_name.clone();
+        let db_server_url = self.db_server_url.clone();
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build cleanup runtime")
+                .block_on(drop_database(&db_server_url, &db_name));
+        })
+        .join()
+        .expect("Failed to drop test database");
+    }
+}
+
+impl TestApp {
+    pub async fn post_login(&self, user: &TestUser) -> reqwest::Response {
+        self.post_login_with(&self.api_client, user).await
+    }
+
+    pub async fn post_login_with(
+        &self,
+        client: &reqwest::Client,
+        user: &TestUser,
+    ) -> reqwest::Response {
+        client
+            .post(format!("{}/login", &self.address))
+            .json(user)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Stores a new user in the database and logs them in, keeping the
+    // session cookie on the api_client for subsequent requests.
+    pub async fn login_test_user(&self) -> TestUser {
+        self.login_test_user_with(&self.api_client).await
+    }
+
+    // Stores a new user without logging in, for tests that call the models
+    // directly and only need the user's id.
+    pub async fn create_test_user_id(&self) -> i32 {
+        let connection = PgConnection::connect(&self.db_url)
+            .await
+            .expect("Failed to connect to Postgres");
+        let test_user = create_test_user(connection).await;
+        let mut connection = PgConnection::connect(&self.db_url)
+            .await
+            .expect("Failed to connect to Postgres");
+        sqlx::query_as::<_, (i32,)>("SELECT id FROM users WHERE email = $1")
+            .bind(&test_user.email)
+            .fetch_one(&mut connection)
+            .await
+            .expect("Failed to fetch test user.")
+            .0
+    }
+
+    // Same as login_test_user, for tests that need a second user with their
+    // own client and session.
+    pub async fn login_test_user_with(&self, client: &reqwest::Client) -> TestUser {
+        let connection = PgConnection::connect(&self.db_url)
+            .await
+            .expect("Failed to connect to Postgres");
+        let test_user = create_test_user(connection).await;
+        let response = self.post_login_with(client, &test_user).await;
+        assert_eq!(200, response.status().as_u16());
+        test_user
+    }
+
+    // Same as login_test_user, with the user given the admin role first.
+    pub async fn login_admin_user(&self) -> TestUser {
+        let connection = PgConnection::connect(&self.db_url)
+            .await
+            .expect("Failed to connect to Postgres");
+        let test_user = create_test_user(connection).await;
+        let mut connection = PgConnection::connect(&self.db_url)
+            .await
+            .expect("Failed to connect to Postgres");
+        sqlx::query("UPDATE users SET role = 'admin' WHERE email = $1")
+            .bind(&test_user.email)
+            .execute(&mut connection)
+            .await
+            .expect("Failed to make test user an admin.");
+        let response = self.post_login(&test_user).await;
+        assert_eq!(200, response.status().as_u16());
+        test_user
+    }
 }
 
 pub async fn spawn_app() -> TestApp {
@@ -20,39 +166,143 @@ pub async fn spawn_app() -> TestApp {
     let port = listener.local_addr().unwrap().port();
     let address = format!("http://127.0.0.1:{}", port);
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
-
-    let connection = PgConnection::connect(&configuration.database.connection_string())
-        .await
-        .expect("Failed to connect to Postgres");
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    // Every test gets its own freshly migrated database
+    configuration.database.database_name = format!("test_{}", Uuid::new_v4().to_simple());
+    configure_database(&configuration.database).await;
 
-    let app_data = AppData::init(&configuration).await;
+    let mut app_data = AppData::init(&configuration).await;
+    let mailer = Arc::new(InMemoryMailer::default());
+    app_data.mailer = mailer.clone();
     let hmac_secret = configuration.hmac_secret;
     let redis_uri = configuration.redis_uri;
-    let server = run(listener, app_data, hmac_secret, redis_uri)
+    let server = run(listener, app_data.clone(), hmac_secret, redis_uri)
         .await
         .unwrap();
-    let _ = tokio::spawn(server);
+    tokio::spawn(server);
 
-    let test_app = TestApp {
-        address: address,
+    TestApp {
+        address,
         db_url: configuration.database.connection_string(),
-    };
+        app_data,
+        api_client: build_api_client(),
+        mailer,
+        db_server_url: configuration.database.connection_string_without_db(),
+        db_name: configuration.database.database_name,
+    }
+}
 
-    test_app
+async fn configure_database(config: &DatabaseSettings) {
+    let mut connection = PgConnection::connect(&config.connection_string_without_db())
+        .await
+        .expect("Failed to connect to Postgres");
+    connection
+        .execute(format!(r#"CREATE DATABASE "{}";"#, config.database_name).as_str())
+        .await
+        .expect("Failed to create database.");
+
+    let pool = PgPool::connect(&config.connection_string())
+        .await
+        .expect("Failed to connect to Postgres.");
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("Failed to migrate the database");
+    pool.close().await;
+}
+
+async fn drop_database(db_server_url: &str, db_name: &str) {
+    let mut connection = PgConnection::connect(db_server_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    connection
+        .execute(format!(r#"DROP DATABASE "{}" WITH (FORCE);"#, db_name).as_str())
+        .await
+        .expect("Failed to drop database.");
+}
+
+pub fn build_api_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .cookie_provider(Arc::new(TestCookieJar::default()))
+        .build()
+        .unwrap()
+}
+
+// Rates every skill in ids with the same rating, for DiaryForm bodies
+pub fn rated_skills(ids: &[i32], rating: i32) -> Vec<SkillRating> {
+    ids.iter()
+        .map(|&skill_id| SkillRating {
+            skill_id,
+            rating,
+            steps: vec![],
+        })
+        .collect()
+}
+
+// Adds six global skills, two from each of the first three categories:
+// observe (1), describe (2), activities (3), contributing (4), sleep (5), eating (6)
+pub async fn create_test_data(connection: PgConnection) {
+    let query = r#"INSERT INTO skills (name, category, description)
+       VALUES
+       ('observe', 'mindfulness', ''),
+       ('describe', 'mindfulness', ''),
+       ('activities', 'distress_tolerance', ''),
+       ('contributing', 'distress_tolerance', ''),
+       ('sleep', 'emotion_regulation', ''),
+       ('eating', 'emotion_regulation', '')"#;
+    let mut pg_connection = connection;
+    pg_connection
+        .execute(query)
+        .await
+        .expect("Unable to add skills to database");
+}
+
+// Posts a diary entry as the api_client's user for date, given as YYYY-MM-DD
+pub async fn create_entry(app: &TestApp, date: &str, skills: &[SkillRating]) -> DiaryEntry {
+    create_entry_with_notes(app, date, skills, "").await
+}
+
+pub async fn create_entry_with_notes(
+    app: &TestApp,
+    date: &str,
+    skills: &[SkillRating],
+    notes: &str,
+) -> DiaryEntry {
+    let naive_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: skills.to_vec(),
+        notes: notes.to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct TestUser {
-    pub username: String,
+    pub email: String,
+    pub name: String,
     pub password: String,
 }
 
 impl TestUser {
     pub fn generate() -> Self {
         Self {
-            username: Uuid::new_v4().to_string(),
-            password: "password".to_string(),
+            email: format!("{}@example.com", Uuid::new_v4()),
+            name: Uuid::new_v4().to_string(),
+            // Strong enough to pass the password policy when signing up
+            password: "Tumbleweed-Lantern-42".to_string(),
         }
     }
 }
@@ -70,10 +320,12 @@ pub async fn create_test_user(mut connection: PgConnection) -> TestUser {
     .unwrap()
     .to_string();
 
-    let query = "INSERT INTO users (username, password_hash)
-            VALUES ($1, $2)";
+    // Test users have already verified their address
+    let query = "INSERT INTO users (email, name, password_hash, email_verified_at)
+            VALUES ($1, $2, $3, now())";
     sqlx::query(query)
-        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.name)
         .bind(password_hash)
         .execute(&mut connection)
         .await
@@ -84,18 +336,11 @@ pub async fn create_test_user(mut connection: PgConnection) -> TestUser {
 #[actix_rt::test]
 async fn login_user() {
     let app = spawn_app().await;
-    let client = reqwest::Client::new();
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let user_connection = PgConnection::connect(&configuration.database.connection_string())
+    let user_connection = PgConnection::connect(&app.db_url)
         .await
         .expect("Failed to connect to Postgres");
     let test_user: TestUser = create_test_user(user_connection).await;
 
-    let create_response = client
-        .post(&format!("{}/login", &app.address))
-        .json(&test_user)
-        .send()
-        .await
-        .expect("Failed to execute request.");
+    let create_response = app.post_login(&test_user).await;
     assert_eq!(200, create_response.status().as_u16());
 }
@@ -0,0 +1,129 @@
+use crate::helpers::{create_entry, create_test_data, rated_skills, spawn_app, TestApp};
+use shooting_star::models::RatedSkill;
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn get_json(app: &TestApp, path: &str) -> serde_json::Value {
+    let response = app
+        .api_client
+        .get(format!("{}{}", &app.address, path))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16(), "{}", path);
+    response.json().await.unwrap()
+}
+
+// The names of the skills embedded in each entry on a page
+fn skill_names(page: &serde_json::Value) -> Vec<Vec<&str>> {
+    page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| {
+            entry["skills"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|skill| skill["name"].as_str().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn show_embeds_rated_skills_when_asked() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    let mut connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    sqlx::query("UPDATE skills SET description = 'Notice without words' WHERE id = 1")
+        .execute(&mut connection)
+        .await
+        .expect("Failed to describe skill.");
+    app.login_test_user().await;
+    create_entry(&app, "2022-02-07", &rated_skills(&[1, 3], 6)).await;
+
+    let body = get_json(&app, "/diary_entries/2022-02-07").await;
+    assert!(body.get("skills").is_none());
+
+    let body = get_json(&app, "/diary_entries/2022-02-07?include=skills").await;
+    let skills: Vec<RatedSkill> = serde_json::from_value(body["skills"].clone()).unwrap();
+    let observe = &skills[0];
+    assert_eq!(skills.len(), 2);
+    assert_eq!(observe.skill_id, 1);
+    assert_eq!(observe.name, "observe");
+    assert_eq!(observe.category, "mindfulness");
+    assert_eq!(observe.description, "Notice without words");
+    assert_eq!(observe.rating, Some(6));
+
+    let response = app
+        .api_client
+        .get(format!(
+            "{}/diary_entries/2022-02-07?include=urges",
+            &app.address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn index_embeds_each_entrys_rated_skills() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    create_entry(&app, "2022-02-07", &rated_skills(&[1], 6)).await;
+    create_entry(&app, "2022-02-08", &[]).await;
+    create_entry(&app, "2022-02-09", &rated_skills(&[1, 3], 6)).await;
+
+    let page = get_json(&app, "/diary_entries?include=skills").await;
+    assert_eq!(
+        skill_names(&page),
+        vec![vec!["observe"], vec![], vec!["observe", "activities"]]
+    );
+
+    let page = get_json(&app, "/diary_entries").await;
+    assert!(page["items"][0].get("skills").is_none());
+}
+
+#[actix_rt::test]
+async fn embedded_skills_follow_their_entry_in_any_order() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    // Ids don't follow the dates, like an entry backfilled for an earlier day
+    create_entry(&app, "2022-02-09", &rated_skills(&[1, 3], 6)).await;
+    create_entry(&app, "2022-02-07", &rated_skills(&[1], 6)).await;
+    create_entry(&app, "2022-02-08", &rated_skills(&[3], 6)).await;
+
+    let page = get_json(&app, "/diary_entries?include=skills").await;
+    assert_eq!(
+        skill_names(&page),
+        vec![
+            vec!["observe"],
+            vec!["activities"],
+            vec!["observe", "activities"]
+        ]
+    );
+
+    let page = get_json(&app, "/diary_entries?include=skills&sort=desc").await;
+    assert_eq!(
+        skill_names(&page),
+        vec![
+            vec!["observe", "activities"],
+            vec!["activities"],
+            vec!["observe"]
+        ]
+    );
+}
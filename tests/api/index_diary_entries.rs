@@ -0,0 +1,126 @@
+use crate::helpers::{
+    create_entry, create_entry_with_notes, create_test_data, rated_skills, spawn_app, TestApp,
+};
+use shooting_star::controllers::Page;
+use shooting_star::models::DiaryEntry;
+use sqlx::{postgres::PgConnection, Connection};
+
+// Creates a week of entries: notes on the 2nd and 4th, observe on the 1st-3rd, activities on the 5th
+async fn create_week(app: &TestApp) {
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    create_entry(app, "2022-02-01", &rated_skills(&[1], 4)).await;
+    create_entry_with_notes(app, "2022-02-02", &rated_skills(&[1], 4), "a hard day").await;
+    create_entry(app, "2022-02-03", &rated_skills(&[1], 4)).await;
+    create_entry_with_notes(app, "2022-02-04", &[], "better").await;
+    create_entry(app, "2022-02-05", &rated_skills(&[3], 4)).await;
+}
+
+async fn index(app: &TestApp, query: &str) -> (u16, Option<Page<DiaryEntry>>) {
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries?{}", &app.address, query))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status().as_u16();
+    if status != 200 {
+        return (status, None);
+    }
+    (status, Some(response.json().await.unwrap()))
+}
+
+async fn dates(app: &TestApp, query: &str) -> Vec<String> {
+    let (status, page) = index(app, query).await;
+    assert_eq!(200, status, "query {}", query);
+    page.unwrap()
+        .items
+        .iter()
+        .map(|entry| entry.entry_date.to_string())
+        .collect()
+}
+
+#[actix_rt::test]
+async fn new_entries_are_listed_and_ranges_may_be_open_ended() {
+    let app = spawn_app().await;
+    create_week(&app).await;
+
+    assert_eq!(dates(&app, "").await.len(), 5);
+    assert_eq!(
+        dates(&app, "start=2022-02-04").await,
+        vec!["2022-02-04", "2022-02-05"]
+    );
+    assert_eq!(
+        dates(&app, "end=2022-02-02").await,
+        vec!["2022-02-01", "2022-02-02"]
+    );
+    assert_eq!(
+        dates(&app, "start=2022-02-02&end=2022-02-03").await,
+        vec!["2022-02-02", "2022-02-03"]
+    );
+}
+
+#[actix_rt::test]
+async fn entries_can_be_filtered_by_notes_skill_and_category() {
+    let app = spawn_app().await;
+    create_week(&app).await;
+
+    assert_eq!(
+        dates(&app, "has_notes=true").await,
+        vec!["2022-02-02", "2022-02-04"]
+    );
+    assert_eq!(
+        dates(&app, "has_notes=false").await,
+        vec!["2022-02-01", "2022-02-03", "2022-02-05"]
+    );
+    assert_eq!(
+        dates(&app, "skill_id=1&has_notes=false").await,
+        vec!["2022-02-01", "2022-02-03"]
+    );
+    assert_eq!(
+        dates(&app, "category=distress_tolerance").await,
+        vec!["2022-02-05"]
+    );
+}
+
+#[actix_rt::test]
+async fn entries_are_paged_with_a_cursor_in_either_order() {
+    let app = spawn_app().await;
+    create_week(&app).await;
+
+    let mut pages = Vec::new();
+    let mut query = "sort=desc&limit=2".to_string();
+    loop {
+        let (_, page) = index(&app, &query).await;
+        let page = page.unwrap();
+        pages.push(
+            page.items
+                .iter()
+                .map(|entry| entry.entry_date.to_string())
+                .collect::<Vec<String>>(),
+        );
+        match page.next_cursor {
+            Some(cursor) => query = format!("sort=desc&limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(
+        pages,
+        vec![
+            vec!["2022-02-05", "2022-02-04"],
+            vec!["2022-02-03", "2022-02-02"],
+            vec!["2022-02-01"],
+        ]
+    );
+
+    assert_eq!(
+        dates(&app, "limit=2&cursor=2022-02-02").await,
+        vec!["2022-02-03", "2022-02-04"]
+    );
+    for query in ["limit=0", "limit=201", "sort=sideways", "has_notes=maybe"] {
+        assert_eq!(index(&app, query).await.0, 400, "query {}", query);
+    }
+}
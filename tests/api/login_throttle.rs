@@ -0,0 +1,179 @@
+use crate::helpers::{build_api_client, create_test_user, spawn_app, TestApp, TestUser};
+use shooting_star::configuration::LoginThrottleSettings;
+use shooting_star::login_throttle::LoginThrottle;
+use sqlx::{postgres::PgConnection, Connection};
+
+fn settings() -> LoginThrottleSettings {
+    LoginThrottleSettings {
+        max_failures_per_email: 2,
+        max_failures_per_ip: 3,
+        window_seconds: 60,
+        base_lockout_seconds: 10,
+        max_lockout_seconds: 25,
+    }
+}
+
+async fn stored_user(app: &TestApp) -> TestUser {
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_user(connection).await
+}
+
+fn with_wrong_password(user: &TestUser) -> TestUser {
+    TestUser {
+        email: user.email.clone(),
+        name: user.name.clone(),
+        password: "not the password".to_string(),
+    }
+}
+
+fn retry_after(response: &reqwest::Response) -> u64 {
+    response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[actix_rt::test]
+async fn repeated_failures_lock_the_email_out_with_retry_after() {
+    let app = spawn_app().await;
+    let user = stored_user(&app).await;
+    let wrong = with_wrong_password(&user);
+
+    // Five failures are allowed by the test configuration
+    for _ in 0..5 {
+        let response = app.post_login(&wrong).await;
+        assert_eq!(303, response.status().as_u16());
+    }
+    let response = app.post_login(&wrong).await;
+    assert_eq!(429, response.status().as_u16());
+    assert_eq!(retry_after(&response), 30);
+
+    // Locked out even with the right password
+    let response = app.post_login(&user).await;
+    assert_eq!(429, response.status().as_u16());
+    assert!((1..=30).contains(&retry_after(&response)));
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("Too many"));
+
+    // Other accounts from the same address can still log in
+    app.login_test_user_with(&build_api_client()).await;
+}
+
+#[actix_rt::test]
+async fn logging_in_clears_the_emails_failures() {
+    let app = spawn_app().await;
+    let user = stored_user(&app).await;
+    let wrong = with_wrong_password(&user);
+
+    for _ in 0..2 {
+        for _ in 0..4 {
+            let response = app.post_login(&wrong).await;
+            assert_eq!(303, response.status().as_u16());
+        }
+        let response = app.post_login(&user).await;
+        assert_eq!(200, response.status().as_u16());
+    }
+}
+
+#[actix_rt::test]
+async fn lockouts_double_up_to_the_maximum_per_email_and_ip() {
+    let throttle = LoginThrottle::in_memory(settings(), "test");
+
+    assert_eq!(
+        throttle.record_failure("a@example.com", "10.0.0.1").await,
+        None
+    );
+    assert_eq!(
+        throttle.record_failure("A@example.com", "10.0.0.1").await,
+        None
+    );
+    assert_eq!(
+        throttle.retry_after("a@example.com", "10.0.0.1").await,
+        None
+    );
+
+    assert_eq!(
+        throttle.record_failure("a@example.com", "10.0.0.1").await,
+        Some(10)
+    );
+    assert_eq!(
+        throttle.retry_after("a@example.com", "10.0.0.2").await,
+        Some(10)
+    );
+    // The address's allowance runs out too, at half the email's lockout
+    assert_eq!(
+        throttle.record_failure("a@example.com", "10.0.0.1").await,
+        Some(20)
+    );
+    assert_eq!(
+        throttle.retry_after("b@example.com", "10.0.0.1").await,
+        Some(10)
+    );
+    assert_eq!(
+        throttle.record_failure("a@example.com", "10.0.0.1").await,
+        Some(25)
+    );
+
+    // Logging in later forgets the failures, not a lockout in progress
+    throttle.record_success("a@example.com").await;
+    assert_eq!(
+        throttle.retry_after("a@example.com", "10.0.0.2").await,
+        Some(25)
+    );
+    assert_eq!(
+        throttle.retry_after("b@example.com", "10.0.0.2").await,
+        None
+    );
+}
+
+#[actix_rt::test]
+async fn attempts_are_counted_in_memory_without_redis() {
+    // Nothing listens on port 1
+    let throttle = LoginThrottle::connect(settings(), "redis://127.0.0.1:1", "test").await;
+    for _ in 0..2 {
+        assert_eq!(
+            throttle.record_failure("a@example.com", "10.0.0.1").await,
+            None
+        );
+    }
+    assert_eq!(
+        throttle.record_failure("a@example.com", "10.0.0.1").await,
+        Some(10)
+    );
+    assert_eq!(
+        throttle.retry_after("a@example.com", "10.0.0.1").await,
+        Some(10)
+    );
+}
+
+#[actix_rt::test]
+async fn guessing_the_current_password_locks_out_password_changes() {
+    let app = spawn_app().await;
+    let user = app.login_test_user().await;
+    let change_password = |current_password: &str| {
+        app.api_client
+            .post(format!("{}/account/password", &app.address))
+            .json(&serde_json::json!({
+                "current_password": current_password,
+                "new_password": "Quiet-Harbour-Lamp-7",
+            }))
+            .send()
+    };
+
+    for _ in 0..5 {
+        let response = change_password("not the password").await.unwrap();
+        assert_eq!(401, response.status().as_u16());
+    }
+    let response = change_password("not the password").await.unwrap();
+    assert_eq!(429, response.status().as_u16());
+    assert_eq!(retry_after(&response), 30);
+
+    // The right password has to wait too, and so does logging in
+    let response = change_password(&user.password).await.unwrap();
+    assert_eq!(429, response.status().as_u16());
+    let response = app.post_login_with(&build_api_client(), &user).await;
+    assert_eq!(429, response.status().as_u16());
+}
@@ -1,5 +1,28 @@
+mod admin_skills;
 mod authentication;
 mod create_skill_entry;
+mod custom_skills;
+mod delete_diary_entry;
+mod diary_cards;
+mod diary_entry_ownership;
+mod diary_entry_upsert;
+mod email_verification;
+mod emotions_and_urges;
 mod health_check;
 mod helpers;
+mod include_skills;
+mod index_diary_entries;
+mod login_throttle;
+mod password_policy;
+mod passwords;
+mod query_parameters;
+mod seeds;
 mod show_diary_entry;
+mod signup;
+mod skill_catalogue;
+mod skill_categories;
+mod skill_ratings;
+mod skill_steps;
+mod stats;
+mod streaks;
+mod unit_of_work;
@@ -0,0 +1,122 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use shooting_star::controllers::credentials_controller::SignupErrorResponse;
+use shooting_star::password_policy::{strength, BreachedPasswords};
+use std::path::Path;
+
+async fn post_json(app: &TestApp, path: &str, body: serde_json::Value) -> reqwest::Response {
+    app.api_client
+        .post(format!("{}{}", &app.address, path))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+// The password errors a signup with the given password gets back
+async fn signup_password_errors(app: &TestApp, password: &str) -> Vec<String> {
+    let user = TestUser {
+        password: password.to_string(),
+        ..TestUser::generate()
+    };
+    let response = post_json(app, "/signup", serde_json::to_value(&user).unwrap()).await;
+    assert_eq!(400, response.status().as_u16(), "{}", password);
+    let error: SignupErrorResponse = response.json().await.unwrap();
+    error.fields["password"].clone()
+}
+
+#[test]
+fn breached_passwords_are_found_by_hash_prefix() {
+    let breached = BreachedPasswords::load(Path::new("data/breached_passwords.txt")).unwrap();
+    assert!(breached.contains("password"));
+    assert!(breached.contains("P@ssw0rd"));
+    assert!(!breached.contains("Tumbleweed-Lantern-42"));
+
+    // sha1("password") is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+    let range = breached.range("5BAA6").unwrap();
+    assert!(range.contains("1E4C9B93F3F0682250B6CF8331B7EE68FD8"));
+}
+
+#[test]
+fn guessable_patterns_lower_the_strength_score() {
+    assert_eq!(strength("kx8v3pqz", &[]).score, 4);
+    for (password, feedback) in [
+        ("p4ssw0rd", "avoid common passwords and words"),
+        ("aaaaaaaaaaaa", "avoid repeated characters like aaa"),
+        ("abcdefgh", "avoid sequences like abc, 123 or qwerty"),
+        ("zxcvbnm1", "avoid sequences like abc, 123 or qwerty"),
+        ("janejane", "avoid using your name or email address"),
+    ] {
+        let strength = strength(password, &["jane@example.com"]);
+        assert!(strength.score < 3, "{}", password);
+        assert!(strength.feedback.contains(&feedback), "{}", password);
+    }
+}
+
+#[actix_rt::test]
+async fn signup_explains_why_a_password_is_rejected() {
+    let app = spawn_app().await;
+
+    let errors = signup_password_errors(&app, "password1").await;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("data breach"));
+
+    let errors = signup_password_errors(&app, "12345678abc").await;
+    assert!(errors[0].starts_with("is too easy to guess"));
+    assert!(errors[0].contains("sequences"));
+
+    let errors = signup_password_errors(&app, "kx8v").await;
+    assert_eq!(errors, vec!["must be at least 8 characters"]);
+}
+
+#[actix_rt::test]
+async fn password_changes_and_resets_follow_the_policy() {
+    let app = spawn_app().await;
+    let user = app.login_test_user().await;
+
+    // The policy is only explained to someone who knows the current password
+    let response = post_json(
+        &app,
+        "/account/password",
+        serde_json::json!({ "current_password": "wrong", "new_password": "qwerty123" }),
+    )
+    .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = post_json(
+        &app,
+        "/account/password",
+        serde_json::json!({ "current_password": user.password, "new_password": "qwerty123" }),
+    )
+    .await;
+    assert_eq!(400, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    assert!(error["error"].as_str().unwrap().contains("data breach"));
+
+    let response = post_json(
+        &app,
+        "/password/forgot",
+        serde_json::json!({ "email": user.email }),
+    )
+    .await;
+    assert_eq!(202, response.status().as_u16());
+    let body = app.mailer.sent().last().unwrap().body.clone();
+    let (_, rest) = body.split_once("token=").unwrap();
+    let token = rest.split_whitespace().next().unwrap();
+
+    let response = post_json(
+        &app,
+        "/password/reset",
+        serde_json::json!({ "token": token, "password": "aaaaaaaaaaaa" }),
+    )
+    .await;
+    assert_eq!(400, response.status().as_u16());
+
+    // A rejected password leaves the token usable
+    let response = post_json(
+        &app,
+        "/password/reset",
+        serde_json::json!({ "token": token, "password": "kx8v3pqz-harbour" }),
+    )
+    .await;
+    assert_eq!(204, response.status().as_u16());
+}
@@ -0,0 +1,148 @@
+use crate::helpers::{build_api_client, spawn_app, TestApp, TestUser};
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn post_json(app: &TestApp, path: &str, body: serde_json::Value) -> reqwest::Response {
+    app.api_client
+        .post(format!("{}{}", &app.address, path))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn can_log_in(app: &TestApp, email: &str, password: &str) -> bool {
+    let user = TestUser {
+        email: email.to_string(),
+        name: "".to_string(),
+        password: password.to_string(),
+    };
+    app.post_login_with(&build_api_client(), &user)
+        .await
+        .status()
+        .is_success()
+}
+
+// Asks for a reset link and returns the token from the emailed link
+async fn forgot_password(app: &TestApp, email: &str) -> String {
+    let response = post_json(
+        app,
+        "/password/forgot",
+        serde_json::json!({ "email": email }),
+    )
+    .await;
+    assert_eq!(202, response.status().as_u16());
+    let sent = app.mailer.sent();
+    let email = sent.last().expect("No email was sent");
+    let (_, rest) = email.body.split_once("token=").unwrap();
+    rest.split_whitespace().next().unwrap().to_string()
+}
+
+#[actix_rt::test]
+async fn changing_the_password_requires_the_current_one() {
+    let app = spawn_app().await;
+    let user = app.login_test_user().await;
+
+    let response = post_json(
+        &app,
+        "/account/password",
+        serde_json::json!({ "current_password": "wrong", "new_password": "new password" }),
+    )
+    .await;
+    assert_eq!(401, response.status().as_u16());
+    assert!(can_log_in(&app, &user.email, &user.password).await);
+
+    let response = post_json(
+        &app,
+        "/account/password",
+        serde_json::json!({ "current_password": user.password, "new_password": "new password" }),
+    )
+    .await;
+    assert_eq!(204, response.status().as_u16());
+    assert!(!can_log_in(&app, &user.email, &user.password).await);
+    assert!(can_log_in(&app, &user.email, "new password").await);
+
+    let response = build_api_client()
+        .post(format!("{}/account/password", &app.address))
+        .json(&serde_json::json!({ "current_password": "x", "new_password": "y" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn reset_tokens_are_emailed_and_work_once() {
+    let app = spawn_app().await;
+    let user = app.login_test_user().await;
+
+    // Unknown addresses get the same response and no email
+    let response = post_json(
+        &app,
+        "/password/forgot",
+        serde_json::json!({ "email": "nobody@example.com" }),
+    )
+    .await;
+    assert_eq!(202, response.status().as_u16());
+    assert!(app.mailer.sent().is_empty());
+
+    let token = forgot_password(&app, &user.email).await;
+    assert_eq!(app.mailer.sent()[0].to, user.email);
+
+    // Only a hash of the token is stored
+    let mut connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    let (token_hash,): (String,) = sqlx::query_as("SELECT token_hash FROM password_reset_tokens")
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+    assert_ne!(token_hash, token);
+
+    let body = serde_json::json!({ "token": token, "password": "new password" });
+    let response = post_json(&app, "/password/reset", body.clone()).await;
+    assert_eq!(204, response.status().as_u16());
+    assert!(can_log_in(&app, &user.email, "new password").await);
+
+    let response = post_json(&app, "/password/reset", body).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn expired_and_superseded_reset_tokens_are_rejected() {
+    let app = spawn_app().await;
+    let user = app.login_test_user().await;
+    let mut connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+
+    let expired = forgot_password(&app, &user.email).await;
+    sqlx::query("UPDATE password_reset_tokens SET expires_at = now() - interval '1 minute'")
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    let response = post_json(
+        &app,
+        "/password/reset",
+        serde_json::json!({ "token": expired, "password": "new password" }),
+    )
+    .await;
+    assert_eq!(400, response.status().as_u16());
+
+    // Changing the password revokes links sent before the change
+    let superseded = forgot_password(&app, &user.email).await;
+    let response = post_json(
+        &app,
+        "/account/password",
+        serde_json::json!({ "current_password": user.password, "new_password": "changed again" }),
+    )
+    .await;
+    assert_eq!(204, response.status().as_u16());
+    let response = post_json(
+        &app,
+        "/password/reset",
+        serde_json::json!({ "token": superseded, "password": "new password" }),
+    )
+    .await;
+    assert_eq!(400, response.status().as_u16());
+    assert!(can_log_in(&app, &user.email, "changed again").await);
+}
@@ -0,0 +1,129 @@
+use crate::helpers::{create_test_data, spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::models::{save_from_form, DateRangeRequest, DiaryEntry, Skill, UnitOfWork};
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn create_entries(app: &TestApp, user_id: i32, dates: &[&str]) {
+    let mut unit_of_work = UnitOfWork::begin(&app.app_data).await.unwrap();
+    for date in dates {
+        let naive_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+        save_from_form(&datetime_utc, "", &mut unit_of_work, &user_id)
+            .await
+            .unwrap();
+    }
+    unit_of_work.commit().await.unwrap();
+}
+
+fn date(date: &str) -> Option<NaiveDate> {
+    Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+}
+
+#[actix_rt::test]
+async fn index_rejects_sql_shaped_dates() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    for query in [
+        "start=2022-01-01'%20OR%20'1'='1&end=2022-12-31",
+        "start=2022-01-01&end=2022-12-31;%20DROP%20TABLE%20diary_entries;--",
+        "start=0&end=99999999999",
+    ] {
+        let response = app
+            .api_client
+            .get(format!("{}/diary_entries?{}", &app.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(400, response.status().as_u16(), "query {}", query);
+    }
+}
+
+#[actix_rt::test]
+async fn create_rejects_non_integer_skill_ids() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    let body = serde_json::json!({
+        "entry_date": "2022-02-07T00:00:00Z",
+        "skills": [{ "skill_id": "1) OR (1=1", "rating": 0 }],
+        "notes": "",
+    });
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn date_range_is_bound_and_scoped_to_the_user() {
+    let app = spawn_app().await;
+    let user_id = app.create_test_user_id().await;
+    let other_user_id = app.create_test_user_id().await;
+    create_entries(&app, user_id, &["2022-01-31", "2022-02-01", "2022-02-28"]).await;
+    create_entries(&app, other_user_id, &["2022-02-01"]).await;
+
+    let in_february = DateRangeRequest {
+        start: date("2022-02-01"),
+        end: date("2022-02-28"),
+    };
+    let entries = DiaryEntry::find_by_date_range_user(&app.app_data, in_february, &user_id)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| entry.user_id == user_id));
+
+    let widest_range = DateRangeRequest {
+        start: date("0001-01-01"),
+        end: date("9999-12-31"),
+    };
+    let entries = DiaryEntry::find_by_date_range_user(&app.app_data, widest_range, &user_id)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+
+    let reversed_range = DateRangeRequest {
+        start: date("2022-02-28"),
+        end: date("2022-02-01"),
+    };
+    let entries = DiaryEntry::find_by_date_range_user(&app.app_data, reversed_range, &user_id)
+        .await
+        .unwrap();
+    assert!(entries.is_empty());
+}
+
+#[actix_rt::test]
+async fn skill_id_list_is_bound_as_an_array() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    let user_id = app.create_test_user_id().await;
+    let mut unit_of_work = UnitOfWork::begin(&app.app_data).await.unwrap();
+
+    let skills = Skill::find_by_ids(&mut unit_of_work, &[i32::MIN, -1, 0, i32::MAX], &user_id)
+        .await
+        .unwrap();
+    assert!(skills.is_empty());
+
+    let skills = Skill::find_by_ids(&mut unit_of_work, &[], &user_id)
+        .await
+        .unwrap();
+    assert!(skills.is_empty());
+
+    let skills = Skill::find_by_ids(&mut unit_of_work, &[1, 1, 3, 3, 5], &user_id)
+        .await
+        .unwrap();
+    assert_eq!(skills.len(), 3);
+
+    let many_ids: Vec<i32> = (1..=100_000).collect();
+    let skills = Skill::find_by_ids(&mut unit_of_work, &many_ids, &user_id)
+        .await
+        .unwrap();
+    assert_eq!(skills.len(), 6);
+}
@@ -0,0 +1,169 @@
+use crate::helpers::spawn_app;
+use shooting_star::seeds::{load_seeds, run_seeds, SeedOptions, SeedOutcome};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// A throwaway seeds directory with one file in each of two sets
+fn write_seed_sets() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("seeds_{}", Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("catalogue")).unwrap();
+    std::fs::create_dir_all(dir.join("demo")).unwrap();
+    std::fs::write(
+        dir.join("catalogue/20230101000000_skills.sql"),
+        "INSERT INTO skills (name, category, description)
+         VALUES ('observe', 'mindfulness', 'first')
+         ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+         DO UPDATE SET description = EXCLUDED.description;",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("demo/20230101000000_users.sql"),
+        "INSERT INTO users (email, name, password_hash)
+         SELECT 'demo@example.com', 'demo', ''
+         WHERE NOT EXISTS (SELECT 1 FROM users WHERE email = 'demo@example.com');",
+    )
+    .unwrap();
+    dir
+}
+
+fn sets(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+async fn count(pool: &sqlx::PgPool, query: &str) -> i64 {
+    sqlx::query_as::<_, (i64,)>(query)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .0
+}
+
+#[actix_rt::test]
+async fn seeds_are_applied_once_and_again_when_changed() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+    let dir = write_seed_sets();
+    let seed_sets = sets(&["catalogue", "demo"]);
+
+    let seeds = load_seeds(&dir, &seed_sets).unwrap();
+    let reports = run_seeds(pool, seeds, &SeedOptions::default())
+        .await
+        .unwrap();
+    let outcomes: Vec<(&str, &SeedOutcome)> = reports
+        .iter()
+        .map(|report| (report.key.as_str(), &report.outcome))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("catalogue/20230101000000_skills", &SeedOutcome::Applied),
+            ("demo/20230101000000_users", &SeedOutcome::Applied)
+        ]
+    );
+
+    let seeds = load_seeds(&dir, &seed_sets).unwrap();
+    let reports = run_seeds(pool, seeds, &SeedOptions::default())
+        .await
+        .unwrap();
+    assert!(reports
+        .iter()
+        .all(|report| report.outcome == SeedOutcome::UpToDate));
+    assert_eq!(count(pool, "SELECT count(*) FROM skills").await, 1);
+
+    // Editing a seed runs it again, upserting rather than duplicating
+    std::fs::write(
+        dir.join("catalogue/20230101000000_skills.sql"),
+        "INSERT INTO skills (name, category, description)
+         VALUES ('observe', 'mindfulness', 'second')
+         ON CONFLICT (name, COALESCE(parent_id, 0)) WHERE user_id IS NULL
+         DO UPDATE SET description = EXCLUDED.description;",
+    )
+    .unwrap();
+    let seeds = load_seeds(&dir, &seed_sets).unwrap();
+    let reports = run_seeds(pool, seeds, &SeedOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(reports[0].outcome, SeedOutcome::Reapplied);
+    assert_eq!(reports[1].outcome, SeedOutcome::UpToDate);
+    assert_eq!(
+        count(
+            pool,
+            "SELECT count(*) FROM skills WHERE description = 'second'"
+        )
+        .await,
+        1
+    );
+    assert_eq!(count(pool, "SELECT count(*) FROM skills").await, 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_rt::test]
+async fn dry_run_and_only_limit_what_is_written() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+    let dir = write_seed_sets();
+    let seed_sets = sets(&["catalogue", "demo"]);
+
+    let options = SeedOptions {
+        dry_run: true,
+        only: None,
+    };
+    let reports = run_seeds(pool, load_seeds(&dir, &seed_sets).unwrap(), &options)
+        .await
+        .unwrap();
+    assert!(reports
+        .iter()
+        .all(|report| report.outcome == SeedOutcome::WouldApply));
+    assert_eq!(count(pool, "SELECT count(*) FROM skills").await, 0);
+    assert_eq!(count(pool, "SELECT count(*) FROM seed_runs").await, 0);
+
+    let options = SeedOptions {
+        dry_run: false,
+        only: Some("users".to_string()),
+    };
+    let reports = run_seeds(pool, load_seeds(&dir, &seed_sets).unwrap(), &options)
+        .await
+        .unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].key, "demo/20230101000000_users");
+    assert_eq!(count(pool, "SELECT count(*) FROM skills").await, 0);
+    assert_eq!(count(pool, "SELECT count(*) FROM users").await, 1);
+
+    let options = SeedOptions {
+        dry_run: false,
+        only: Some("missing".to_string()),
+    };
+    assert!(
+        run_seeds(pool, load_seeds(&dir, &seed_sets).unwrap(), &options)
+            .await
+            .is_err()
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_rt::test]
+async fn the_skill_catalogue_can_be_seeded_repeatedly() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+    let seed_sets = sets(&["catalogue"]);
+
+    let seeds = load_seeds(Path::new("seeds"), &seed_sets).unwrap();
+    run_seeds(pool, seeds, &SeedOptions::default())
+        .await
+        .unwrap();
+    let skills = count(pool, "SELECT count(*) FROM skills").await;
+    assert!(skills > 0);
+
+    // Forget the runs so every file executes again
+    sqlx::query("DELETE FROM seed_runs")
+        .execute(pool)
+        .await
+        .unwrap();
+    let seeds = load_seeds(Path::new("seeds"), &seed_sets).unwrap();
+    run_seeds(pool, seeds, &SeedOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(count(pool, "SELECT count(*) FROM skills").await, skills);
+}
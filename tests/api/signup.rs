@@ -0,0 +1,67 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use shooting_star::controllers::credentials_controller::SignupErrorResponse;
+
+async fn post_signup(app: &TestApp, body: &serde_json::Value) -> reqwest::Response {
+    app.api_client
+        .post(format!("{}/signup", &app.address))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn an_email_can_only_sign_up_once_whatever_its_case() {
+    let app = spawn_app().await;
+    let user = TestUser::generate();
+    let response = post_signup(&app, &serde_json::to_value(&user).unwrap()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let body = serde_json::json!({
+        "email": user.email.to_uppercase(),
+        "name": "someone else",
+        "password": "another password",
+    });
+    let response = post_signup(&app, &body).await;
+    assert_eq!(409, response.status().as_u16());
+    let error: SignupErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.fields["email"], vec!["is already registered"]);
+
+    // Logging in matches the address the same way
+    let login = TestUser {
+        email: user.email.to_uppercase(),
+        ..user
+    };
+    let response = app.post_login(&login).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn every_invalid_field_is_reported() {
+    let app = spawn_app().await;
+
+    let body = serde_json::json!({
+        "email": "not an email",
+        "name": "  ",
+        "password": "short",
+    });
+    let response = post_signup(&app, &body).await;
+    assert_eq!(400, response.status().as_u16());
+    let error: SignupErrorResponse = response.json().await.unwrap();
+    let fields: Vec<&str> = error.fields.keys().map(String::as_str).collect();
+    assert_eq!(fields, vec!["email", "name", "password"]);
+
+    let body = serde_json::json!({
+        "email": "long.name@example.com",
+        "name": "x".repeat(101),
+        "password": "long enough",
+    });
+    let response = post_signup(&app, &body).await;
+    assert_eq!(400, response.status().as_u16());
+    let error: SignupErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        error.fields["name"],
+        vec!["can't be longer than 100 characters"]
+    );
+    assert!(!error.fields.contains_key("email"));
+}
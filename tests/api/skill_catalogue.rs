@@ -0,0 +1,154 @@
+use crate::helpers::spawn_app;
+use shooting_star::catalogue::{self, CatalogueError, CatalogueFormat};
+
+const CATALOGUE_YAML: &str = "- name: observe
+  category: mindfulness
+  description: Notice without words
+- name: DEAR MAN
+  category: interpersonal_effectiveness
+  description: Getting what you want
+- name: describe
+  category: interpersonal_effectiveness
+  description: Describe the situation
+  parent: DEAR MAN
+";
+
+#[actix_rt::test]
+async fn a_catalogue_round_trips_through_every_format() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+
+    let rows = catalogue::parse(CATALOGUE_YAML, CatalogueFormat::Yaml).unwrap();
+    let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+    assert_eq!(lines, vec![1, 4, 7]);
+    assert!(catalogue::validate(pool, &rows).await.unwrap().is_empty());
+    assert_eq!(catalogue::import(pool, &rows, false).await.unwrap(), 3);
+    // Importing again updates in place rather than adding duplicates
+    catalogue::import(pool, &rows, false).await.unwrap();
+
+    let exported = catalogue::export(pool).await.unwrap();
+    let imported: Vec<_> = rows.into_iter().map(|(_, row)| row).collect();
+    assert_eq!(exported, imported);
+    for format in [
+        CatalogueFormat::Yaml,
+        CatalogueFormat::Json,
+        CatalogueFormat::Csv,
+    ] {
+        let text = catalogue::render(&exported, format).unwrap();
+        let parsed: Vec<_> = catalogue::parse(&text, format)
+            .unwrap()
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect();
+        assert_eq!(parsed, exported, "{:?} did not round trip", format);
+    }
+}
+
+#[actix_rt::test]
+async fn validation_errors_point_at_the_offending_line() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+
+    let csv = "name,category,description,parent
+observe,mindfulness,,
+describe,mindfulness,,
+observe,mindfulness,,
+describe,feelings,,
+step one,mindfulness,,WISE MIND
+";
+    let rows = catalogue::parse(csv, CatalogueFormat::Csv).unwrap();
+    let errors = catalogue::validate(pool, &rows).await.unwrap();
+    let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+    assert_eq!(lines, vec![4, 5, 5, 6]);
+    assert_eq!(
+        errors[0].to_string(),
+        "line 4: 'observe' is already in the catalogue on line 2"
+    );
+    assert_eq!(errors[1].to_string(), "line 5: unknown category 'feelings'");
+
+    // A quoted description can run over several lines
+    let csv = "name,category,description,parent
+observe,mindfulness,\"Notice
+without words\",
+describe,feelings,,
+";
+    let rows = catalogue::parse(csv, CatalogueFormat::Csv).unwrap();
+    let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+    assert_eq!(lines, vec![2, 4]);
+    let errors = catalogue::validate(pool, &rows).await.unwrap();
+    assert_eq!(errors[0].to_string(), "line 4: unknown category 'feelings'");
+
+    let json = r#"[
+  {"name": "observe", "category": "mindfulness"},
+  {
+    "name": "{not a brace}",
+    "category": "feelings"
+  }
+]"#;
+    let rows = catalogue::parse(json, CatalogueFormat::Json).unwrap();
+    let errors = catalogue::validate(pool, &rows).await.unwrap();
+    assert_eq!(
+        errors,
+        vec![CatalogueError {
+            line: 3,
+            message: "unknown category 'feelings'".to_string()
+        }]
+    );
+
+    let yaml = "- name: observe
+  category: mindfulness
+- name: describe
+";
+    let error = catalogue::parse(yaml, CatalogueFormat::Yaml).unwrap_err();
+    assert_eq!(error.line, 3);
+    assert!(error.message.contains("category"));
+
+    // Nothing was written while validating
+    assert!(catalogue::export(pool).await.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn the_catalogue_can_be_downloaded() {
+    let app = spawn_app().await;
+    let pool = &app.app_data.pg_pool;
+    let rows = catalogue::parse(CATALOGUE_YAML, CatalogueFormat::Yaml).unwrap();
+    catalogue::import(pool, &rows, false).await.unwrap();
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills/export", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+
+    app.login_test_user().await;
+    for (format, content_type) in [
+        ("json", "application/json"),
+        ("yaml", "application/yaml"),
+        ("csv", "text/csv"),
+    ] {
+        let response = app
+            .api_client
+            .get(format!("{}/skills/export?format={}", &app.address, format))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(200, response.status().as_u16());
+        assert_eq!(
+            response.headers()["Content-Type"].to_str().unwrap(),
+            content_type
+        );
+        let text = response.text().await.unwrap();
+        let parsed = catalogue::parse(&text, format.parse().unwrap()).unwrap();
+        assert_eq!(parsed.len(), 3);
+    }
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills/export?format=xlsx", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,99 @@
+use crate::helpers::{create_test_data, spawn_app};
+use shooting_star::controllers::{SkillCategoryForm, SkillForm};
+use shooting_star::models::{Skill, SkillCategory};
+use sqlx::{postgres::PgConnection, Connection};
+
+#[actix_rt::test]
+async fn skill_categories_are_listed_in_card_order() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/skill_categories", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let categories: Vec<SkillCategory> = response.json().await.unwrap();
+    let names: Vec<&str> = categories
+        .iter()
+        .map(|category| category.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "mindfulness",
+            "distress_tolerance",
+            "emotion_regulation",
+            "interpersonal_effectiveness"
+        ]
+    );
+    assert_eq!(categories[1].label, "Distress Tolerance");
+}
+
+#[actix_rt::test]
+async fn skills_can_be_filtered_by_category() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills?category=mindfulness", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let skills: Vec<Skill> = response.json().await.unwrap();
+    let names: Vec<&str> = skills.iter().map(|skill| skill.name.as_str()).collect();
+    assert_eq!(names, vec!["observe", "describe"]);
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills?category=mindfullness", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let skills: Vec<Skill> = response.json().await.unwrap();
+    assert!(skills.is_empty());
+}
+
+#[actix_rt::test]
+async fn skills_with_an_unknown_category_are_rejected() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_admin_user().await;
+    let body = SkillForm {
+        name: "wise mind".to_string(),
+        category: "mindfullness".to_string(),
+        description: "".to_string(),
+    };
+
+    for path in ["skills", "admin/skills"] {
+        let response = app
+            .api_client
+            .post(format!("{}/{}", &app.address, path))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(400, response.status().as_u16(), "POST /{}", path);
+    }
+
+    let response = app
+        .api_client
+        .put(format!("{}/admin/skills/1/category", &app.address))
+        .json(&SkillCategoryForm {
+            category: "distress-tolerance".to_string(),
+        })
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
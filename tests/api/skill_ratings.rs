@@ -0,0 +1,94 @@
+use crate::helpers::{create_test_data, spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::{DiaryForm, SkillRating};
+use shooting_star::models::DiaryEntrySkills;
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn post_diary_entry(app: &TestApp, skills: Vec<SkillRating>) -> reqwest::Response {
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills,
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    app.api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn skill_ratings_are_returned_with_diary_entry_skills() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    let skills = vec![
+        SkillRating {
+            skill_id: 1,
+            rating: 0,
+            steps: vec![],
+        },
+        SkillRating {
+            skill_id: 3,
+            rating: 7,
+            steps: vec![],
+        },
+    ];
+    let response = post_diary_entry(&app, skills).await;
+    assert_eq!(201, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let mut diary_entry_skills: Vec<DiaryEntrySkills> = response.json().await.unwrap();
+    diary_entry_skills.sort_by_key(|skill| skill.skills_id);
+    let ratings: Vec<(i32, Option<i32>)> = diary_entry_skills
+        .iter()
+        .map(|skill| (skill.skills_id, skill.rating))
+        .collect();
+    assert_eq!(ratings, vec![(1, Some(0)), (3, Some(7))]);
+}
+
+#[actix_rt::test]
+async fn ratings_outside_the_configured_scale_are_rejected() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+
+    for rating in [-1, 8] {
+        let skills = vec![SkillRating {
+            skill_id: 1,
+            rating,
+            steps: vec![],
+        }];
+        let response = post_diary_entry(&app, skills).await;
+        assert_eq!(400, response.status().as_u16(), "rating {}", rating);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("scale"));
+    }
+
+    // Nothing is written for a rejected form
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
@@ -0,0 +1,122 @@
+use crate::helpers::{spawn_app, TestApp};
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::controllers::{DiaryForm, SkillRating};
+use shooting_star::models::{DiaryEntrySkills, Skill};
+use sqlx::Executor;
+use sqlx::{postgres::PgConnection, Connection};
+
+// DEAR MAN is skill 1 with steps 2 to 4, GIVE is skill 5 with step 6
+async fn create_test_data(app: &TestApp) {
+    let query = r#"INSERT INTO skills (name, category, description, parent_id)
+       VALUES
+       ('DEAR MAN', 'interpersonal_effectiveness', '', NULL),
+       ('describe', 'interpersonal_effectiveness', '', 1),
+       ('express', 'interpersonal_effectiveness', '', 1),
+       ('assert', 'interpersonal_effectiveness', '', 1),
+       ('GIVE', 'interpersonal_effectiveness', '', NULL),
+       ('gentle', 'interpersonal_effectiveness', '', 5)"#;
+    let mut pg_connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    pg_connection
+        .execute(query)
+        .await
+        .expect("Unable to add skills to database");
+}
+
+#[actix_rt::test]
+async fn acronym_skills_are_shown_with_their_steps() {
+    let app = spawn_app().await;
+    create_test_data(&app).await;
+    app.login_test_user().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let skills: Vec<Skill> = response.json().await.unwrap();
+    let names: Vec<&str> = skills.iter().map(|skill| skill.name.as_str()).collect();
+    assert_eq!(names, vec!["DEAR MAN", "GIVE"]);
+
+    let response = app
+        .api_client
+        .get(format!("{}/skills/1", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let tree: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(tree["name"], "DEAR MAN");
+    let steps: Vec<Skill> = serde_json::from_value(tree["steps"].clone()).unwrap();
+    let steps: Vec<(&str, Option<i32>)> = steps
+        .iter()
+        .map(|step| (step.name.as_str(), step.parent_id))
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            ("describe", Some(1)),
+            ("express", Some(1)),
+            ("assert", Some(1))
+        ]
+    );
+}
+
+#[actix_rt::test]
+async fn steps_used_are_logged_with_their_skill() {
+    let app = spawn_app().await;
+    create_test_data(&app).await;
+    app.login_test_user().await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+    let body = DiaryForm {
+        entry_date: datetime_utc,
+        skills: vec![
+            // Step 6 belongs to GIVE, so it is not recorded against DEAR MAN
+            SkillRating {
+                skill_id: 1,
+                rating: 5,
+                steps: vec![4, 2, 6],
+            },
+            SkillRating {
+                skill_id: 5,
+                rating: 2,
+                steps: vec![],
+            },
+            // Steps are only logged through their acronym
+            SkillRating {
+                skill_id: 3,
+                rating: 2,
+                steps: vec![],
+            },
+        ],
+        notes: "".to_string(),
+        emotions: vec![],
+        urges: vec![],
+    };
+    let response = app
+        .api_client
+        .post(format!("{}/diary_entries", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+
+    let response = app
+        .api_client
+        .get(format!("{}/diary_entries/2022-02-07/skills", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let mut diary_entry_skills: Vec<DiaryEntrySkills> = response.json().await.unwrap();
+    diary_entry_skills.sort_by_key(|skill| skill.skills_id);
+    let logged: Vec<(i32, Vec<i32>)> = diary_entry_skills
+        .into_iter()
+        .map(|skill| (skill.skills_id, skill.steps))
+        .collect();
+    assert_eq!(logged, vec![(1, vec![2, 4]), (5, vec![])]);
+}
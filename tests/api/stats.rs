@@ -0,0 +1,105 @@
+use crate::helpers::{create_entry, create_test_data, rated_skills, spawn_app, TestApp};
+use shooting_star::models::{SkillStats, Stats};
+use sqlx::{postgres::PgConnection, Connection};
+
+async fn get_stats(app: &TestApp, query: &str) -> Stats {
+    let response = app
+        .api_client
+        .get(format!("{}/stats?{}", &app.address, query))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+fn summary(skills: &[SkillStats]) -> Vec<(&str, i64, Option<f64>, i64)> {
+    skills
+        .iter()
+        .map(|skill| {
+            (
+                skill.name.as_str(),
+                skill.uses,
+                skill.average_rating,
+                skill.longest_streak,
+            )
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn usage_is_aggregated_per_skill_and_category() {
+    let app = spawn_app().await;
+    let connection = PgConnection::connect(&app.db_url)
+        .await
+        .expect("Failed to connect to Postgres");
+    create_test_data(connection).await;
+    app.login_test_user().await;
+    create_entry(
+        &app,
+        "2022-02-01",
+        &[rated_skills(&[1], 4), rated_skills(&[3], 2)].concat(),
+    )
+    .await;
+    create_entry(&app, "2022-02-02", &rated_skills(&[1], 6)).await;
+    create_entry(&app, "2022-02-03", &rated_skills(&[1], 2)).await;
+    create_entry(
+        &app,
+        "2022-02-05",
+        &[rated_skills(&[1], 4), rated_skills(&[2], 4)].concat(),
+    )
+    .await;
+
+    let stats = get_stats(&app, "").await;
+    assert_eq!(stats.entries, 4);
+    assert_eq!(
+        summary(&stats.skills),
+        vec![
+            ("observe", 4, Some(4.0), 3),
+            ("describe", 1, Some(4.0), 1),
+            ("activities", 1, Some(2.0), 1),
+            ("contributing", 0, None, 0),
+            ("sleep", 0, None, 0),
+            ("eating", 0, None, 0),
+        ]
+    );
+    let categories: Vec<(&str, i64, Option<f64>)> = stats
+        .categories
+        .iter()
+        .map(|category| {
+            (
+                category.category.as_str(),
+                category.uses,
+                category.average_rating,
+            )
+        })
+        .collect();
+    assert!(categories.contains(&("mindfulness", 5, Some(4.0))));
+    assert!(categories.contains(&("distress_tolerance", 1, Some(2.0))));
+    assert!(categories.contains(&("emotion_regulation", 0, None)));
+    assert_eq!(stats.most_used[0].name, "observe");
+    let least_used: Vec<&str> = stats
+        .least_used
+        .iter()
+        .map(|skill| skill.name.as_str())
+        .collect();
+    assert_eq!(
+        least_used,
+        vec!["eating", "sleep", "contributing", "activities", "describe"]
+    );
+
+    let stats = get_stats(&app, "start=2022-02-03").await;
+    assert_eq!(stats.entries, 2);
+    assert_eq!(
+        summary(&stats.skills),
+        vec![
+            ("observe", 2, Some(3.0), 1),
+            ("describe", 1, Some(4.0), 1),
+            ("activities", 0, None, 0),
+            ("contributing", 0, None, 0),
+            ("sleep", 0, None, 0),
+            ("eating", 0, None, 0),
+        ]
+    );
+    assert_eq!(summary(&stats.most_used).len(), 2);
+}
@@ -0,0 +1,88 @@
+use crate::helpers::{create_entry, spawn_app, TestApp};
+use chrono::Duration;
+use shooting_star::controllers::credentials_controller::WhoAmI;
+use shooting_star::controllers::SettingsForm;
+use shooting_star::models::Streaks;
+
+async fn set_timezone(app: &TestApp, timezone: &str) -> u16 {
+    let body = SettingsForm {
+        timezone: Some(timezone.to_string()),
+        ..Default::default()
+    };
+    app.api_client
+        .patch(format!("{}/account/settings", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+async fn get_streaks(app: &TestApp) -> Streaks {
+    let response = app
+        .api_client
+        .get(format!("{}/streaks", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn streaks_count_consecutive_days_up_to_today() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+    let today = get_streaks(&app).await.today;
+    for days_ago in [9, 8, 7, 6, 2, 1] {
+        create_entry(&app, &(today - Duration::days(days_ago)).to_string(), &[]).await;
+    }
+
+    // Today isn't filled in yet, so the streak runs to yesterday
+    let streaks = get_streaks(&app).await;
+    assert_eq!((streaks.current, streaks.longest), (2, 4));
+    assert!((streaks.completion_30_days - 6.0 / 30.0).abs() < 1e-9);
+    assert!((streaks.completion_90_days - 6.0 / 90.0).abs() < 1e-9);
+
+    create_entry(&app, &today.to_string(), &[]).await;
+    let streaks = get_streaks(&app).await;
+    assert_eq!((streaks.current, streaks.longest), (3, 4));
+
+    let response = app
+        .api_client
+        .get(format!("{}/whoami", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let whoami: WhoAmI = response.json().await.unwrap();
+    assert_eq!(whoami.streaks, streaks);
+}
+
+#[actix_rt::test]
+async fn days_are_counted_in_the_users_timezone() {
+    let app = spawn_app().await;
+    app.login_test_user().await;
+
+    // 25 hours apart, so Kiritimati is always a day or two ahead of Pago Pago
+    assert_eq!(200, set_timezone(&app, "Pacific/Pago_Pago").await);
+    let pago_pago_today = get_streaks(&app).await.today;
+    for days in [-1, 0, 1] {
+        create_entry(
+            &app,
+            &(pago_pago_today + Duration::days(days)).to_string(),
+            &[],
+        )
+        .await;
+    }
+    let streaks = get_streaks(&app).await;
+    assert_eq!((streaks.current, streaks.longest), (2, 2));
+
+    assert_eq!(200, set_timezone(&app, "Pacific/Kiritimati").await);
+    let streaks = get_streaks(&app).await;
+    assert!(streaks.today > pago_pago_today);
+    assert_eq!((streaks.current, streaks.longest), (3, 3));
+
+    assert_eq!(400, set_timezone(&app, "Mars/Olympus_Mons").await);
+}
@@ -0,0 +1,72 @@
+use crate::helpers::spawn_app;
+use chrono::{DateTime, NaiveDate, Utc};
+use shooting_star::models::{save_from_form, DiaryEntry, DiaryEntrySkills, Skill, UnitOfWork};
+
+fn test_skill() -> Skill {
+    Skill {
+        id: 1,
+        name: "observe".to_string(),
+        category: "mindfulness".to_string(),
+        description: "".to_string(),
+        user_id: None,
+        parent_id: None,
+    }
+}
+
+#[actix_rt::test]
+async fn dropped_unit_of_work_rolls_back_entry_and_skill_links() {
+    let app = spawn_app().await;
+    let app_data = &app.app_data;
+    let user_id = app.create_test_user_id().await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+
+    let entry_id = {
+        let mut unit_of_work = UnitOfWork::begin(app_data).await.unwrap();
+        let entry = save_from_form(&datetime_utc, "", &mut unit_of_work, &user_id)
+            .await
+            .unwrap();
+        DiaryEntrySkills::save_diary_entry_skill(&mut unit_of_work, &test_skill(), 3, &[], &entry)
+            .await
+            .unwrap();
+        entry.id
+    };
+
+    let found = DiaryEntry::find_by_date(app_data, naive_date, &user_id).await;
+    assert!(matches!(found, Err(sqlx::Error::RowNotFound)));
+    let skills =
+        DiaryEntrySkills::find_diary_entry_skills_by_diary_id(app_data, entry_id, &user_id)
+            .await
+            .unwrap();
+    assert!(skills.is_empty());
+}
+
+#[actix_rt::test]
+async fn committed_unit_of_work_persists_entry_and_skill_links() {
+    let app = spawn_app().await;
+    let app_data = &app.app_data;
+    let user_id = app.create_test_user_id().await;
+
+    let naive_date = NaiveDate::parse_from_str("2022-02-07", "%Y-%m-%d").unwrap();
+    let datetime_utc = DateTime::<Utc>::from_utc(naive_date.and_hms(0, 0, 0), Utc);
+
+    let mut unit_of_work = UnitOfWork::begin(app_data).await.unwrap();
+    let entry = save_from_form(&datetime_utc, "", &mut unit_of_work, &user_id)
+        .await
+        .unwrap();
+    DiaryEntrySkills::save_diary_entry_skill(&mut unit_of_work, &test_skill(), 3, &[], &entry)
+        .await
+        .unwrap();
+    unit_of_work.commit().await.unwrap();
+
+    let found = DiaryEntry::find_by_date(app_data, naive_date, &user_id)
+        .await
+        .unwrap();
+    assert_eq!(found.id, entry.id);
+    let skills =
+        DiaryEntrySkills::find_diary_entry_skills_by_diary_id(app_data, entry.id, &user_id)
+            .await
+            .unwrap();
+    assert_eq!(skills.len(), 1);
+}